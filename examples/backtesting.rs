@@ -8,21 +8,28 @@ use rs_backtester::utilities::serialize_to_csv;
 fn main()->Result<(),Box<dyn Error>>{
     //example to calculate backtesting results
     let quotes = Data::new_from_yahoo("NVDA","1d","6mo")?;
-    let sma_cross_strategy = sma_cross(quotes.clone(), 10,20);
-    let sma_cross_tester_zero_comm = Backtest::new(quotes.clone(),sma_cross_strategy.clone(),100000f64, Commission::default());
+    let sma_cross_strategy = sma_cross(quotes.clone(), 10,20)?;
+    let sma_cross_tester_zero_comm = Backtest::new(quotes.clone(),sma_cross_strategy.clone(),100000f64, Commission::default())?;
     println!("---------------------------");
     println!("With zero commission rate");
     println!("---------------------------");
-    report(sma_cross_tester_zero_comm);
+    report(sma_cross_tester_zero_comm)?;
 
     //now let's see if strategy is still profitable with a custom commission rate of 1% on every trade
-    let mut commission = Commission::default();
-    commission.rate = 0.01;
+    let rate = 0.01;
+    let commission = Commission::Rate(rate);
 
-    let sma_cross_tester = Backtest::new(quotes.clone(),sma_cross_strategy.clone(),100000f64, commission.clone());
+    let sma_cross_tester = Backtest::new(quotes.clone(),sma_cross_strategy.clone(),100000f64, commission.clone())?;
     println!("---------------------------");
-    println!("Now with {:.2}% commission rate",commission.rate*100.);
+    println!("Now with {:.2}% commission rate",rate*100.);
     println!("---------------------------");
-    report(sma_cross_tester);
+    report(sma_cross_tester)?;
+
+    //or with a broker charging 0.005 per share, at least 1 and at most 1% of the order value
+    let per_share = Backtest::new(quotes.clone(),sma_cross_strategy.clone(),100000f64, Commission::PerShareCapped{fee:0.005, min:1., max_rate:0.01})?;
+    println!("---------------------------");
+    println!("Now with a per share commission");
+    println!("---------------------------");
+    report(per_share)?;
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,18 @@
+use std::error::Error;
+use rs_backtester::backtester::Commission;
+use rs_backtester::datas::Data;
+use rs_backtester::portfolio::{align, PortfolioBacktest};
+use rs_backtester::strategies::{buy_n_hold, sma_cross};
+
+fn main()->Result<(),Box<dyn Error>>{
+    //example to backtest several assets sharing the same account
+    let universe = align(&[Data::new_from_yahoo("AAPL","1d","1y")?, Data::new_from_yahoo("MSFT","1d","1y")?, Data::new_from_yahoo("NVDA","1d","1y")?])?;
+    let strategies = vec![buy_n_hold(universe[0].clone()), sma_cross(universe[1].clone(),10,20)?, sma_cross(universe[2].clone(),10,20)?];
+    let portfolio = PortfolioBacktest::new(universe, strategies, vec![0.4,0.3,0.3], 100000., Commission::default())?;
+    for (asset,ticker) in portfolio.tickers().iter().enumerate(){
+        println!("{} - final position = {}",ticker,portfolio.position(asset).last().unwrap());
+    }
+    println!("cash = {:.2}",portfolio.cash().last().unwrap());
+    println!("net worth = {:.2}",portfolio.networth().last().unwrap());
+    Ok(())
+}
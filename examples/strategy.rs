@@ -1,5 +1,6 @@
 use std::error::Error;
 use rs_backtester::datas::Data;
+use rs_backtester::series::Series;
 use rs_backtester::strategies::Strategy;
 use rs_backtester::orders::Order::{BUY,SHORTSELL,NULL};
 extern crate rand;
@@ -17,21 +18,17 @@ pub fn main() -> Result<(),Box<dyn Error>>{
         let length = quotes.timestamps().len();
         let mut choices = vec![NULL;length];
         let name = "random strategy".to_string();
-        let indicator = Some(vec![vec![-1.;length]]);
+        let indicator = Some(vec![Series::missing(length)]);
         let rnd_orders= vec![BUY,SHORTSELL,NULL];
         for i in 0..length{
             let mut rng = thread_rng();
                     choices[i] = *rnd_orders.choose(&mut rng).unwrap();
             }
-        Strategy{
-            name:name,
-            choices:choices,
-            indicator,
-        }
+        Strategy::new(&name, choices, indicator)
     }
 
     let rnd_strategy = random_strategy(quotes.clone());
-    let random_backtester = Backtest::new(quotes, rnd_strategy, 1e5, Commission::default());
-    report(random_backtester);
+    let random_backtester = Backtest::new(quotes, rnd_strategy, 1e5, Commission::default())?;
+    report(random_backtester)?;
     Ok(())
 }
\ No newline at end of file
@@ -1,83 +1,536 @@
 use std::collections::HashMap;
 use std::env;
+use chrono::{DateTime, Datelike, FixedOffset};
 use csv::Writer;
-use std::error::Error;
+use crate::series::Series;
 use crate::strategies::Strategy;
 use crate::datas::Data;
-use crate::orders::Order;
+use crate::orders::{Order, OrderRequest};
+use crate::errors::{BacktestError, Result};
+use crate::execution::{FillTiming, Slippage};
+use crate::sizing::{PositionSizer, QuantityRule};
+use crate::stops::{Stop, StopRules};
+use crate::trades::{ExitReason, Side, Trade};
+use crate::events::{self, Context, Fill};
 
-///To create a Backtest use Backtest::new()
+///accessor of the indicator of a Strategy, as listed by Backtest::log()
+type IndicatorFn = fn(&Strategy)->Option<Vec<Series>>;
+
+///To create a Backtest use Backtest::new() or Backtest::with_config()
 #[derive(Clone)]
 pub struct Backtest{
     quotes:Data,
     strategy:Strategy,
     position:Vec<f64>,
     account:Vec<f64>,
-    commission: Commission,
+    initial_capital:f64,
+    flows:Vec<f64>,
+    config:BacktestConfig,
+    trades:Vec<Trade>,
+    fills:Vec<Fill>,
 }
 
-///Define the commission scheme.
-/// At the moment only allows a unique fixed rate commission for both long and short positions.
-#[derive(Clone)]
-pub struct Commission{
-    pub rate:f64,
+///Settings of a Backtest: modify the default to change them and pass it to Backtest::with_config()
+#[derive(Clone, Default)]
+pub struct BacktestConfig{
+    pub commission:Commission,
+    pub slippage:Slippage,
+    pub fill_timing:FillTiming,
+    ///quantities that can be traded (whole shares by default)
+    pub quantity_rule:QuantityRule,
+    ///amount invested on every entry (the whole net worth by default)
+    pub position_sizer:PositionSizer,
+    ///protective exits of every position (the ones of the Strategy are used instead if set)
+    pub stops:StopRules,
+    ///deposits (positive) and withdrawals (negative) credited to the account at open of the first bar on or after the given date
+    pub cash_flows:Vec<(DateTime<FixedOffset>,f64)>,
+}
+
+///Define the commission scheme, applied on every order (entry and exit, long and short).<BR>
+///Default is a zero rate
+#[derive(Clone, Debug, PartialEq)]
+pub enum Commission{
+    ///proportional to the value traded (e.g. 0.001 = 0.1%)
+    Rate(f64),
+    ///fixed fee per share, bounded by a minimum and an optional maximum per order
+    PerShare{fee:f64, min:f64, max:Option<f64>},
+    ///fixed fee per share, at least min per order and at most max_rate of the value traded
+    ///(e.g. 0.005 per share, at least 1 and at most 0.01 = 1% of the order value)
+    PerShareCapped{fee:f64, min:f64, max_rate:f64},
+    ///fixed fee per order (ticket)
+    PerTicket(f64),
+    ///rate depending on the value already traded in the calendar month:
+    ///tiers are (monthly value threshold, rate) sorted by threshold, e.g. vec![(0.,0.002),(1e6,0.001)]
+    Tiered(Vec<(f64,f64)>),
 }
 
 impl Default for Commission{
     fn default() -> Self {
-        Self{rate:0.,
+        Commission::Rate(0.)
+    }
+}
+
+impl Commission{
+    ///Returns the commission due on an order of quantity shares at price,
+    ///given the value already traded in the month (only used by Commission::Tiered)
+    pub fn fee(&self, quantity:f64, price:f64, monthly_volume:f64)->f64{
+        let quantity = quantity.abs();
+        if quantity == 0. {return 0.;}
+        match self{
+            Commission::Rate(rate)=>quantity*price*rate,
+            Commission::PerShare{fee,min,max}=>(quantity*fee).max(*min).min(max.unwrap_or(f64::INFINITY)),
+            Commission::PerShareCapped{fee,min,max_rate}=>(quantity*fee).max(*min).min(quantity*price*max_rate),
+            Commission::PerTicket(fee)=>*fee,
+            Commission::Tiered(tiers)=>{
+                let rate = tiers.iter().take_while(|(threshold,_)|*threshold<=monthly_volume).last().map(|(_,rate)|*rate).unwrap_or(0.);
+                quantity*price*rate
+            }
         }
     }
+    ///Returns the largest whole quantity whose value plus commission fits in budget (see QuantityRule::affordable() for other quantities)
+    pub fn affordable_quantity(&self, budget:f64, price:f64, monthly_volume:f64)->f64{
+        QuantityRule::default().affordable(budget, price, self, monthly_volume)
+    }
+}
 
+///value traded in the current calendar month, for tiered commissions
+pub(crate) struct MonthlyVolume{
+    month:(i32,u32),
+    value:f64,
 }
 
-#[derive(PartialEq)]
-enum Stance{
-    LONG,
-    NULL,
-    SHORT,
+impl MonthlyVolume{
+    pub(crate) fn new()->Self{
+        MonthlyVolume{month:(0,0), value:0.}
+    }
+    ///Returns the value traded in the month of datetime before the current order
+    pub(crate) fn at(&mut self, datetime:&DateTime<FixedOffset>)->f64{
+        let month = (datetime.year(), datetime.month());
+        if month != self.month{
+            self.month = month;
+            self.value = 0.;
+        }
+        self.value
+    }
+    pub(crate) fn add(&mut self, quantity:f64, price:f64){
+        self.value += quantity.abs()*price;
+    }
+}
+
+///position opened by the engine and not closed yet
+struct OpenTrade{
+    bar:usize,
+    quantity:f64,
+    price:f64,
+    commission:f64,
+    slippage:f64,
+}
+
+impl OpenTrade{
+    ///adds quantity (same side) at price: the entry price becomes the average price
+    fn add(&mut self, quantity:f64, price:f64, commission:f64, slippage:f64){
+        self.price = (self.quantity*self.price+quantity*price)/(self.quantity+quantity);
+        self.quantity += quantity;
+        self.commission += commission;
+        self.slippage += slippage;
+    }
+    ///takes quantity (same side) out of the position, with its share of the entry costs
+    fn split(&mut self, quantity:f64)->OpenTrade{
+        let share = quantity/self.quantity;
+        let part = OpenTrade{bar:self.bar, quantity, price:self.price, commission:self.commission*share, slippage:self.slippage*share};
+        self.quantity -= quantity;
+        self.commission -= part.commission;
+        self.slippage -= part.slippage;
+        part
+    }
+    fn close(self, quotes:&Data, bar:usize, price:f64, commission:f64, slippage:f64, exit_reason:ExitReason)->Trade{
+        let commission = self.commission + commission;
+        Trade{
+            side:if self.quantity > 0. {Side::Long} else {Side::Short},
+            entry_time:quotes.datetime[self.bar],
+            exit_time:quotes.datetime[bar],
+            quantity:self.quantity.abs(),
+            entry_price:self.price,
+            exit_price:price,
+            commission,
+            slippage:self.slippage + slippage,
+            pnl:self.quantity*(price-self.price)-commission,
+            bars_held:bar-self.bar,
+            exit_reason,
+        }
+    }
+}
+
+///protective exit levels of the open position
+#[derive(Copy, Clone)]
+struct StopLevels{
+    stop_loss:Option<f64>,
+    take_profit:Option<f64>,
+    trailing_distance:Option<f64>,
+    ///best price since the entry (highest high for longs, lowest low for shorts)
+    extreme:f64,
+}
+
+///order request waiting to be filled
+struct PendingOrder{
+    ///index in the requests of the Strategy
+    id:usize,
+    request:OrderRequest,
+    ///the stop of a stop-limit order has been reached
+    triggered:bool,
+    ///the parent order is not filled yet
+    waiting:bool,
+    ///filled or cancelled
+    done:bool,
+}
+
+///account, position, pending orders and trade ledger while Backtest::calculate() walks through the bars
+struct Engine<'a>{
+    quotes:&'a Data,
+    config:&'a BacktestConfig,
+    stops:StopRules,
+    volume:MonthlyVolume,
+    cash:f64,
+    position:f64,
+    ///target weight of the position
+    weight:f64,
+    orders:Vec<PendingOrder>,
+    ///number of order requests of the Strategy already submitted
+    submitted:usize,
+    entry:Option<OpenTrade>,
+    levels:Option<StopLevels>,
+    trades:Vec<Trade>,
+    fills:Vec<Fill>,
+}
+
+impl<'a> Engine<'a>{
+    fn new(quotes:&'a Data, config:&'a BacktestConfig, stops:StopRules, cash:f64)->Self{
+        Engine{quotes, config, stops, volume:MonthlyVolume::new(), cash, position:0., weight:0., orders:Vec::new(), submitted:0,
+            entry:None, levels:None, trades:Vec::new(), fills:Vec::new()}
+    }
+    ///adds the order requests not submitted yet (requests are identified by their index)
+    fn submit(&mut self, requests:&[OrderRequest]){
+        for (id,&request) in requests.iter().enumerate().skip(self.submitted){
+            self.orders.push(PendingOrder{id, request, triggered:false, waiting:request.parent.is_some(), done:false});
+        }
+        self.submitted = requests.len();
+        self.orders.sort_by_key(|order|order.request.bar);
+    }
+    ///trades (signed) quantity at the reference price on bar, moved by slippage: a position on the other side is closed first
+    ///and every (partial) round trip is recorded in the trade ledger with exit_reason
+    fn trade(&mut self, bar:usize, quantity:f64, price:f64, exit_reason:ExitReason){
+        if quantity == 0. {return;}
+        if quantity*self.position < 0. && quantity.abs() > self.position.abs(){
+            let closing = -self.position;
+            self.trade(bar, closing, price, exit_reason);
+            self.trade(bar, quantity-closing, price, exit_reason);
+            return;
+        }
+        let fill = self.config.slippage.fill_price(quantity, price, self.quotes, bar);
+        let fee = self.config.commission.fee(quantity, fill, self.volume.at(&self.quotes.datetime[bar]));
+        let slipped = quantity.abs()*(price-fill).abs();
+        self.volume.add(quantity, fill);
+        self.cash -= quantity*fill + fee;
+        self.fills.push(Fill{bar, datetime:self.quotes.datetime[bar], quantity, price:fill, commission:fee, slippage:slipped});
+        if quantity*self.position >= 0.{
+            match self.entry.as_mut(){
+                Some(open_trade)=>open_trade.add(quantity, fill, fee, slipped),
+                None=>self.entry = Some(OpenTrade{bar, quantity, price:fill, commission:fee, slippage:slipped}),
+            }
+            self.position += quantity;
+            self.set_levels(bar);
+        } else if let Some(mut open_trade) = self.entry.take(){
+            self.trades.push(open_trade.split(-quantity).close(self.quotes, bar, fill, fee, slipped, exit_reason));
+            self.position += quantity;
+            if self.position != 0. {self.entry = Some(open_trade);} else {
+                self.levels = None;
+                self.cancel_children();
+            }
+        }
+    }
+    ///sets stop loss and take profit levels from the (average) entry price, keeping the best price of a trailing stop
+    fn set_levels(&mut self, bar:usize){
+        let Some(open_trade) = &self.entry else { return };
+        if self.stops.is_empty() {return;}
+        let side = self.position.signum();
+        let price = open_trade.price;
+        let distance = |stop:Option<Stop>|stop.and_then(|stop|stop.distance(price, self.quotes, bar));
+        self.levels = Some(StopLevels{
+            stop_loss:distance(self.stops.stop_loss).map(|d|price-side*d),
+            take_profit:distance(self.stops.take_profit).map(|d|price+side*d),
+            trailing_distance:distance(self.stops.trailing_stop),
+            extreme:self.levels.map(|levels|levels.extreme).unwrap_or(price),
+        });
+    }
+    ///exits the position if a protective level is crossed on bar (positions entered before bar only)
+    fn check_stops(&mut self, bar:usize){
+        let Some(levels) = self.levels else { return };
+        if self.entry.as_ref().is_none_or(|open_trade|open_trade.bar >= bar) {return;}
+        let side = self.position.signum();
+        let (open,high,low) = (self.quotes.open[bar], self.quotes.high[bar], self.quotes.low[bar]);
+        let (best,worst) = if side > 0. {(high,low)} else {(low,high)};
+        //the binding stop is the closest to the price
+        let trailing = levels.trailing_distance.map(|d|levels.extreme-side*d);
+        let stop = match (levels.stop_loss,trailing){
+            (Some(stop_loss),Some(trailing)) if trailing*side > stop_loss*side=>Some((trailing,ExitReason::TrailingStop)),
+            (Some(stop_loss),_)=>Some((stop_loss,ExitReason::StopLoss)),
+            (None,Some(trailing))=>Some((trailing,ExitReason::TrailingStop)),
+            (None,None)=>None,
+        };
+        let take_profit = levels.take_profit.map(|level|(level,ExitReason::TakeProfit));
+        let exit = if let Some((_,reason)) = stop.filter(|(level,_)|open*side <= level*side) {Some((open,reason))}
+            else if let Some((_,reason)) = take_profit.filter(|(level,_)|open*side >= level*side) {Some((open,reason))}
+            else if let Some(stop) = stop.filter(|(level,_)|worst*side <= level*side) {Some(stop)}
+            else {take_profit.filter(|(level,_)|best*side >= level*side)};
+        match exit{
+            Some((price,reason))=>{
+                self.trade(bar, -self.position, price, reason);
+                self.weight = 0.;
+            }
+            None=>if let Some(levels) = self.levels.as_mut(){
+                levels.extreme = if side > 0. {levels.extreme.max(high)} else {levels.extreme.min(low)};
+            },
+        }
+    }
+    ///rebalances the position to the target weight at the reference price on bar:
+    ///the position is closed when the target is flat or on the other side, scaled in or out otherwise
+    ///(exit_reason is recorded for the quantity closed)
+    fn rebalance(&mut self, bar:usize, target:f64, price:f64, exit_reason:ExitReason){
+        self.weight = target;
+        if self.position != 0. && target*self.position <= 0.{
+            self.trade(bar, -self.position, price, exit_reason);
+        }
+        if target == 0. {return;}
+        //the exposure is the budget of the position sizer scaled by the target weight:
+        //quantity is added so that its value plus commission fits in the missing exposure (at the fill price
+        //of the quantity affordable without slippage) or removed down to the quantity matching the exposure
+        let (commission,quantity_rule) = (&self.config.commission, &self.config.quantity_rule);
+        let networth = self.cash + self.position*price;
+        let exposure = target.abs()*self.config.position_sizer.budget(networth, self.quotes, bar, &self.trades);
+        let current = self.position.abs()*price;
+        let monthly_volume = self.volume.at(&self.quotes.datetime[bar]);
+        let delta = if exposure >= current {
+            let estimate = target.signum()*quantity_rule.affordable(exposure-current, price, commission, monthly_volume);
+            let fill = self.config.slippage.fill_price(estimate, price, self.quotes, bar);
+            target.signum()*quantity_rule.affordable(exposure-current, fill, commission, monthly_volume)
+        } else {
+            -target.signum()*(self.position.abs()-quantity_rule.round(exposure/price).min(self.position.abs()))
+        };
+        self.trade(bar, delta, price, exit_reason);
+    }
+    ///fills the pending orders reached on bar (in order of submission) and drops the expired ones.<BR>
+    ///Orders whose target is the current target weight are left pending
+    fn fill_orders(&mut self, bar:usize){
+        for k in 0..self.orders.len(){
+            let order = &mut self.orders[k];
+            if order.done || order.waiting {continue;}
+            if !order.request.is_active(bar){
+                if order.request.bar < bar {self.cancel(k);}
+                continue;
+            }
+            let target = order.request.target;
+            if target == self.weight {continue;}
+            let Some(price) = order.request.order_type.fill_price(target > self.weight, &mut order.triggered, self.quotes, bar) else { continue };
+            order.done = true;
+            let (id,oco) = (order.id, order.request.oco);
+            self.rebalance(bar, target, price, ExitReason::Order);
+            for other in 0..self.orders.len(){
+                let linked = &mut self.orders[other];
+                if linked.done {continue;}
+                if linked.waiting && linked.request.parent == Some(id){
+                    //children are submitted on the bar of the fill
+                    linked.waiting = false;
+                    linked.request.bar = bar;
+                } else if oco.is_some() && linked.request.oco == oco{
+                    self.cancel(other);
+                }
+            }
+        }
+        self.orders.retain(|order|!order.done);
+    }
+    ///cancels the order at index k and its children
+    fn cancel(&mut self, k:usize){
+        let mut cancelled = vec![k];
+        while let Some(k) = cancelled.pop(){
+            self.orders[k].done = true;
+            let id = self.orders[k].id;
+            cancelled.extend((0..self.orders.len()).filter(|&child|!self.orders[child].done && self.orders[child].request.parent == Some(id)));
+        }
+    }
+    ///cancels the pending children of filled orders (e.g. the stop and take profit of a bracket) once the position
+    ///they were linked to is closed, whatever closed it (signal, protective exit or another order)
+    fn cancel_children(&mut self){
+        for k in 0..self.orders.len(){
+            let order = &self.orders[k];
+            if !order.done && !order.waiting && order.request.parent.is_some() {self.cancel(k);}
+        }
+    }
+    ///records a position still open on the last bar, valued at its close net of exit commission and slippage
+    fn finish(mut self, bar:usize)->Vec<Trade>{
+        if let Some(open_trade) = self.entry.take(){
+            let price = self.quotes.close[bar];
+            let fill = self.config.slippage.fill_price(-self.position, price, self.quotes, bar);
+            let fee = self.config.commission.fee(self.position, fill, self.volume.at(&self.quotes.datetime[bar]));
+            self.trades.push(open_trade.close(self.quotes, bar, fill, fee, self.position.abs()*(price-fill).abs(), ExitReason::EndOfData));
+        }
+        self.trades
+    }
 }
 
 impl Backtest{
-    ///Use to create and calculate a Backtest instance
-    pub fn new(quotes:Data, strategy: Strategy, account:f64, commission: Commission)->Self{
+    ///Use to create and calculate a Backtest instance<BR>
+    ///quotes and strategy choices must have the same (non-zero) length
+    pub fn new(quotes:Data, strategy: Strategy, account:f64, commission: Commission)->Result<Self>{
+        Backtest::with_config(quotes, strategy, account, BacktestConfig{commission, ..Default::default()})
+    }
+    ///Same as Backtest::new() with the settings (commission, cash flows, ...) given in config
+    pub fn with_config(quotes:Data, strategy: Strategy, account:f64, config: BacktestConfig)->Result<Self>{
+        let mut backtest = Backtest::prepare(quotes, strategy, account, config)?;
+        backtest.calculate();
+        Ok(backtest)
+    }
+    ///checks the inputs and returns a Backtest not yet calculated
+    fn prepare(quotes:Data, strategy: Strategy, account:f64, config: BacktestConfig)->Result<Self>{
         let length = quotes.timestamps().len();
+        if length == 0 {return Err(BacktestError::EmptyData);}
+        if strategy.choices.len() != length {return Err(BacktestError::LengthMismatch{expected:length, found:strategy.choices.len()});}
+        if let Some(weights) = &strategy.weights{
+            if weights.len() != length {return Err(BacktestError::LengthMismatch{expected:length, found:weights.len()});}
+        }
+        if let Some(request) = strategy.requests.iter().find(|request|request.bar >= length || !request.target.is_finite()){
+            return Err(BacktestError::InvalidParameter(format!("order request on bar {} with target {}",request.bar,request.target)));
+        }
+        if let Some((id,_)) = strategy.requests.iter().enumerate().find(|(id,request)|request.parent.is_some_and(|parent|parent == *id || parent >= strategy.requests.len())){
+            return Err(BacktestError::InvalidParameter(format!("order request {} has an invalid parent",id)));
+        }
+        let mut flows = vec![0.;length];
+        for (date,amount) in config.cash_flows.iter(){
+            let bar = quotes.datetime.iter().position(|x|x>=date)
+                .ok_or(BacktestError::InvalidParameter(format!("cash flow on {} is after the last bar",date)))?;
+            flows[bar] += amount;
+        }
         let position = vec![0.;length];
+        let initial_capital = account;
         let account = vec![account;length];
-        let mut _backtest = Backtest{
+        let _backtest = Backtest{
             quotes:quotes,
             strategy:strategy,
             position:position,
             account:account,
-            commission:commission,
+            initial_capital,
+            flows,
+            config,
+            trades:Vec::new(),
+            fills:Vec::new(),
         };
-        _backtest.calculate();
-        _backtest
+        Ok(_backtest)
+    }
+    ///Use to create and calculate a Backtest of an event-driven strategy (see events::Strategy), run bar by bar.<BR>
+    ///The target weights and order requests it gives are recorded in the Strategy of the Backtest, with its stops and indicator
+    ///(a vectorized Strategy gives the same results as with Backtest::with_config()).<BR>
+    ///Returns an InvalidParameter error if the strategy gives an invalid command (see events::Context)
+    pub fn event_driven<S:events::Strategy>(quotes:Data, strategy:&mut S, account:f64, config: BacktestConfig)->Result<Self>{
+        let length = quotes.timestamps().len();
+        let mut recorded = Strategy::from_weights(&strategy.name(), vec![0.;length], strategy.indicator());
+        recorded.stops = strategy.stops();
+        let mut backtest = Backtest::prepare(quotes, recorded, account, config)?;
+        strategy.on_start();
+        let mut delivered = 0;
+        let mut error = None;
+        backtest.simulate(|recorded,engine,bar|{
+            if error.is_some() {return;}
+            for fill in engine.fills[delivered..].iter(){
+                strategy.on_fill(fill);
+            }
+            delivered = engine.fills.len();
+            let Some(weights) = recorded.weights.as_mut() else { return };
+            if bar > 0 {weights[bar] = weights[bar-1];}
+            let context = Context::new(engine.quotes, bar, engine.position, engine.cash, recorded.requests.len());
+            strategy.on_bar(&context);
+            let (target,requests) = match context.commands(){
+                Ok(commands)=>commands,
+                Err(e)=>{
+                    error = Some(e);
+                    return;
+                }
+            };
+            if let Some(target) = target {weights[bar] = target;}
+            recorded.requests.extend(requests);
+        });
+        if let Some(e) = error {return Err(e);}
+        backtest.strategy.choices = backtest.strategy.weights().into_iter().map(Order::from_weight).collect();
+        Ok(backtest)
     }
     ///Returns quotes
-    pub fn quotes(&self)->&Data{return &self.quotes}
+    pub fn quotes(&self)->&Data{&self.quotes}
     ///Returns timeserie of orders
-    pub fn orders(&self)->Vec<Order>{return self.strategy.choices()}
+    pub fn orders(&self)->Vec<Order>{self.strategy.choices()}
     ///Returns timeserie of positions
-    pub fn position(&self)->Vec<f64>{return self.position.clone()}
+    pub fn position(&self)->Vec<f64>{self.position.clone()}
     ///Returns timeserie of account values
-    pub fn account(&self)->Vec<f64>{return self.account.clone();}
-    ///Returns commission rate
-    pub fn commission_rate(&self)->f64{return self.commission.rate.clone();}
+    pub fn account(&self)->Vec<f64>{self.account.clone()}
+    ///Returns timeserie of net worth (account + position valued at close)
+    pub fn networth(&self)->Vec<f64>{
+        self.position.iter().zip(self.quotes.close.iter()).zip(self.account.iter()).map(|((p,c),a)|p*c+a).collect()
+    }
+    ///Returns the account value passed to Backtest::new()
+    pub fn initial_capital(&self)->f64{self.initial_capital}
+    ///Returns timeserie of deposits (positive) and withdrawals (negative)
+    pub fn cash_flows(&self)->Vec<f64>{self.flows.clone()}
+    ///Returns initial capital plus all deposits, minus all withdrawals
+    pub fn net_contributions(&self)->f64{self.initial_capital+self.flows.iter().sum::<f64>()}
+    ///Returns timeserie of net contributions (initial capital plus deposits, minus withdrawals, up to every bar)
+    pub fn contributions(&self)->Vec<f64>{
+        self.flows.iter().scan(self.initial_capital,|total,flow|{
+            *total += flow;
+            Some(*total)
+        }).collect()
+    }
+    ///Returns bar by bar returns of net worth, excluding deposits and withdrawals (the first bar is measured against the initial capital).<BR>
+    ///The return of a bar is 0 when the previous net worth is not positive (e.g. after everything was withdrawn)
+    pub fn returns(&self)->Vec<f64>{
+        let networth = self.networth();
+        let mut previous = self.initial_capital;
+        networth.iter().zip(self.flows.iter()).map(|(&value,&flow)|{
+            let ret = if previous > 0. {(value-flow)/previous-1.} else {0.};
+            previous = value;
+            ret
+        }).collect()
+    }
+    ///Returns the growth of the initial capital, compounding returns() (i.e. net worth without deposits and withdrawals)
+    pub fn equity_curve(&self)->Vec<f64>{
+        self.returns().iter().scan(self.initial_capital,|value,r|{
+            *value *= 1.+r;
+            Some(*value)
+        }).collect()
+    }
+    ///Returns the trade ledger (one Trade per round trip)
+    pub fn trades(&self)->Vec<Trade>{self.trades.clone()}
+    ///Returns the executions of the engine (entries, exits and rebalancings) in order
+    pub fn fills(&self)->&[Fill]{&self.fills}
+    ///Returns commission scheme
+    pub fn commission(&self)->&Commission{&self.config.commission}
+    ///Returns slippage model
+    pub fn slippage(&self)->Slippage{self.config.slippage}
+    ///Returns fill timing
+    pub fn fill_timing(&self)->FillTiming{self.config.fill_timing}
+    ///Returns the settings used for the backtest
+    pub fn config(&self)->&BacktestConfig{&self.config}
     ///Returns Strategy
-    pub fn strategy(&self)->Strategy{return self.strategy.clone();}
+    pub fn strategy(&self)->Strategy{self.strategy.clone()}
     ///Function which display the requested log values of the calculations made period by period.<BR>
-    ///Available choices at the moment are: close, open, low, high, position, account, indicator(s, up to 2)
+    ///Available choices at the moment are: close, open, low, high, volume, position, account, indicator(s, up to 2)
     pub fn log(&self, list:&[&str]){
         let mut data_functions: HashMap<&str, fn(&Data)->Vec<f64>>=HashMap::new();
         data_functions.insert("close", Data::close);
         data_functions.insert("open", Data::open);
         data_functions.insert("low",Data::low);
         data_functions.insert("high",Data::high);
+        data_functions.insert("volume",Data::volume);
         let mut backtest_functions: HashMap<&str, fn(&Backtest)->Vec<f64>>=HashMap::new();
         backtest_functions.insert("position",Backtest::position);
         backtest_functions.insert("account",Backtest::account);
-        let mut strategy_function: HashMap<&str, fn(&Strategy)->Option<Vec<Vec<f64>>>>=HashMap::new();
+        let mut strategy_function: HashMap<&str, IndicatorFn>=HashMap::new();
         strategy_function.insert("indicator",Strategy::indicator);
         for i in 0..self.quotes.timestamps().len(){
             print!("Date = {:} - ",&self.quotes.timestamps()[i].format("%Y-%m-%d"));
@@ -87,13 +540,14 @@ impl Backtest{
                     print!("{} = {:.2}  ",j,value)
                 };
                 if let Some(func) = backtest_functions.get(j){
-                    let value = func(&self)[i];
+                    let value = func(self)[i];
                     print!("{} = {:.2}  ",j,value)
                 };
                 if let Some(func) = strategy_function.get(j){
                     let value = func(&self.strategy);
-                    if let Some(first_vec) = value.iter().flatten().next(){print!("{} = {:.2}  ",j,first_vec[i])}
-                    if let Some(second_vec) = value.iter().flatten().skip(1).next(){print!("{} = {:.2}  ",j,second_vec[i])}
+                    for series in value.iter().flatten().take(2){
+                        match series.get(i){Some(x)=>print!("{} = {:.2}  ",j,x), None=>print!("{} = -  ",j)}
+                    }
                     // TODO: extend to n-case
                 }
             }
@@ -101,112 +555,99 @@ impl Backtest{
             println!();
         }
     }
-    ///function which does the actual backtest and stores vectors of (signed) positions and account values
+    ///function which does the actual backtest and stores vectors of (signed) positions and account values.<BR>
+    ///Target weights are executed according to the fill timing (by default weights of bar i-1 at open of bar i), moved by slippage.<BR>
+    ///The position is rebalanced when the target weight of the strategy changes and protective exits are checked on every bar
+    ///(before the rebalancing, after it with FillTiming::NextOpen), then pending order requests are filled;
+    ///every (partial) round trip is recorded in the trade ledger
     fn calculate(&mut self){
-        let mut stance = Stance::NULL;
-        let mut previous_position = 0.;
-        let mut previous_account = self.account[0];
-        for i in 1..self.quotes.timestamps().len(){
-            match self.strategy.choices()[i-1]{
-                Order::BUY=>{
-                    if stance!=Stance::LONG{
-                        let networth = previous_account + previous_position * self.quotes.open()[i]*(1.-previous_position.signum()*self.commission.rate);
-                        self.position[i] = ((networth/(self.quotes.open()[i]*(1.+self.commission.rate))) as i64) as f64;
-                        self.account[i] = networth-self.position[i]*(self.quotes.open()[i]*(1.+self.commission.rate));
-                        stance = Stance::LONG;
-                    } else {
-                        self.position[i] = previous_position;
-                        self.account[i] = previous_account;
-                    }
-                }
-                Order::SHORTSELL=>{
-                    if stance!=Stance::SHORT{
-                        let networth = previous_account + previous_position * self.quotes.open()[i]*(1.-previous_position.signum()*self.commission.rate);
-                        self.position[i] = -((networth/self.quotes.open()[i]*(1.-self.commission.rate)) as i64) as f64;
-                        self.account[i] = networth-self.position[i]*self.quotes.open()[i]*(1.-self.commission.rate);
-                        stance = Stance::SHORT;
-                    } else {
-                        self.position[i] = previous_position;
-                        self.account[i] = previous_account;
-                    }
-                }
-                Order::NULL=>{
-                    if stance!=Stance::NULL{
-                        let networth = previous_account + previous_position * self.quotes.open()[i]*(1.-previous_position.signum()*self.commission.rate);
-                        self.position[i]=0.;
-                        self.account[i]=networth;
-                        stance = Stance::NULL;
-                    } else {
-                        self.position[i] = previous_position;
-                        self.account[i] = previous_account;
-                    }
-                }
+        self.simulate(|_,_,_|{});
+    }
+    ///walks through the bars: on_bar is called with the Strategy and the engine on every bar, after the fills of the bar
+    ///(before the rebalancing with a fill timing without lag) and can add target weights and order requests to the Strategy
+    fn simulate<F:FnMut(&mut Strategy, &Engine, usize)>(&mut self, mut on_bar:F){
+        let fill_timing = self.config.fill_timing;
+        let lag = fill_timing.lag();
+        let length = self.quotes.datetime.len();
+        let strategy = &mut self.strategy;
+        let stops = strategy.stops.unwrap_or(self.config.stops);
+        let mut engine = Engine::new(&self.quotes, &self.config, stops, self.initial_capital);
+        engine.submit(&strategy.requests);
+        let mut signal = 0.;
+        let mut positions = vec![0.;length];
+        let mut account = vec![0.;length];
+        for i in 0..length{
+            engine.cash += self.flows[i];
+            if fill_timing != FillTiming::NextOpen {engine.check_stops(i);}
+            if lag == 0 {
+                on_bar(strategy, &engine, i);
+                engine.submit(&strategy.requests);
+            }
+            let target = if i < lag {0.} else {strategy.weight(i-lag)};
+            if target != signal{
+                engine.rebalance(i, target, fill_timing.price(&self.quotes, i), ExitReason::Signal);
+                signal = target;
             }
-            previous_account = self.account[i];
-            previous_position = self.position[i];
+            if fill_timing == FillTiming::NextOpen {engine.check_stops(i);}
+            engine.fill_orders(i);
+            if lag > 0 {
+                on_bar(strategy, &engine, i);
+                engine.submit(&strategy.requests);
+            }
+            positions[i] = engine.position;
+            account[i] = engine.cash;
         }
+        let fills = std::mem::take(&mut engine.fills);
+        let trades = engine.finish(length-1);
+        self.fills = fills;
+        self.position = positions;
+        self.account = account;
+        self.trades = trades;
     }
     ///Print Backtest to csv.
-    ///Indicator can only be 1 or 2 at the moment
-    pub fn to_csv(&self, filename:&str)->Result<(), Box<dyn Error>>{
+    ///Indicator can only be 1 or 2 at the moment.<BR>
+    ///FLOW is the value of the fills of the bar (negative for buys), COMMISSION and SLIPPAGE the commission and slippage paid
+    ///on them, CASH_FLOW the deposits (withdrawals if negative), FILL_PRICE the average price of the fills of the bar
+    ///(including slippage, empty without fills) and HIST_PRICE the price of the last fill so far
+    pub fn to_csv(&self, filename:&str)->Result<()>{
         let mut wrt = Writer::from_path(filename)?;
-
-        let timestamps_t:Vec<Vec<String>> = self.quotes.timestamps().iter().map(|e|vec![e.to_string()[0..10].to_string()]).collect();
-        let open_t:Vec<Vec<String>> = self.quotes.open().iter().map(|e|vec![e.to_string()]).collect();
-        let close_t:Vec<Vec<String>> = self.quotes.close().iter().map(|e|vec![e.to_string()]).collect();
-        let choices_t:Vec<Vec<String>> = self.strategy.choices().iter().map(|e|vec![e.to_string().to_string()]).collect();
-
-        let position_t:Vec<Vec<String>> = self.position.iter().map(|e|vec![e.to_string()]).collect();
-        let account_t:Vec<Vec<String>> = self.account.iter().map(|e|vec![e.to_string()]).collect();
-
-        let mktvalue_t:Vec<Vec<String>> = self.position.iter().zip(self.quotes().close().iter()).map(|(a,b)|a*b).collect::<Vec<_>>().iter().map(|e|vec![e.to_string()]).collect();
-        let networth_t:Vec<Vec<String>> = (self.position.iter().zip(self.quotes().close().iter())).zip(self.account.iter()).map(|((a,b),c)|a*b+c).collect::<Vec<_>>().iter().map(|e|vec![e.to_string()]).collect();
-
-        let flows:Vec<f64> = std::iter::once(None).chain(self.position.windows(2).zip(self.quotes.open().windows(2))
-                    .map(|(window,window1)|Some(-(window[1]-window[0])*window1[1]))).map(|e|match e{
-            Some(value)=>value,None=>0.}).collect();
-
-        let flows_t:Vec<String> = flows.iter().map(|e|e.to_string()).collect();
-
-        let mut last_value=0.;
-        let hist_price_t:Vec<f64> = flows.iter().zip(self.quotes.open()).scan(0.0,|state,(&i,j)|{
-            if i !=0.0 {last_value = j;}*state=i;
-            Some(last_value)
-        }).collect();
-
-        let commission_t:Vec<String> = std::iter::once(None).chain(self.account.windows(2).zip(flows.windows(2)).map(
-            |(window,window1)|Some(window[0]-window[1]+window1[1]))).map(|e|match e{Some(value)=>(-1.*value).to_string(),None=>0.to_string()}).collect();
-
-                let indicator1_t:Vec<Vec<String>> = self.strategy.indicator().iter().flatten().next().unwrap().iter().map(|e|vec![e.to_string()]).collect();
-
-                let indicator2_t:Option<Vec<Vec<String>>>;
-                match self.strategy.indicator(){
-                    Some(innervec)=>{
-                        if innervec.len() >=2{
-                            indicator2_t = Some(self.strategy.indicator().iter().flatten().skip(1).next().unwrap().iter().map(|e|vec![e.to_string()]).collect());
-                        } else {indicator2_t = None}
-                    }
-                    None=>{
-                        indicator2_t = None;
-                    }
-                }
-
-                if let Some(ind2_t) = indicator2_t{
-                    wrt.serialize(("DATE","OPEN","CLOSE","CHOICES","INDIC1","INDIC2","ACCOUNT","POSITION","MKTVALUE","NETWORTH","FLOW","COMMISSION","HIST_PRICE"))?;
-                    for ((((((((((((col1,col2),col3),col4),col5),col6),col7),col8),col9),col10),col11),col12),col13) in timestamps_t.iter().zip(open_t.iter()).zip(close_t.iter()).zip(choices_t.iter())
-                        .zip(indicator1_t.iter()).zip(ind2_t.iter()).zip(account_t.iter()).zip(position_t.iter()).zip(mktvalue_t.iter()).zip(networth_t.iter()).zip(flows_t.iter()).zip(commission_t.iter()).zip(hist_price_t.iter()){
-                        wrt.serialize((col1,col2,col3,col4,col5,col6,col7,col8,col9,col10,col11,col12,col13))?;
-                    } }
-                else
-                {
-                    wrt.serialize(("DATE","OPEN","CLOSE","CHOICES","INDIC1","ACCOUNT","POSITION","MKTVALUE","NETWORTH","FLOW","COMMISSION","HIST_PRICE"))?;
-                    for (((((((((((col1,col2),col3),col4),col5),col6),col7),col8),col9),col10),col11),col12) in timestamps_t.iter().zip(open_t.iter()).zip(close_t.iter()).zip(choices_t.iter())
-                        .zip(indicator1_t.iter()).zip(account_t.iter()).zip(position_t.iter()).zip(mktvalue_t.iter()).zip(networth_t.iter()).zip(flows_t.iter()).zip(commission_t.iter()).zip(hist_price_t.iter()){
-                        wrt.serialize((col1,col2,col3,col4,col5,col6,col7,col8,col9,col10,col11,col12))?;
-                    }
-                }
-                wrt.flush()?;
-                println!("Backtesting saved as = {:?}",env::current_dir()?.into_os_string().into_string().unwrap()+"\\"+filename);
-                Ok(())
+        let length = self.position.len();
+        let indicators:Vec<Series> = self.strategy.indicator().unwrap_or_default().into_iter().take(2).collect();
+        let mut header = vec!["DATE","OPEN","CLOSE","CHOICES","INDIC1"];
+        if indicators.len() == 2 {header.push("INDIC2");}
+        header.extend(["ACCOUNT","POSITION","MKTVALUE","NETWORTH","FLOW","COMMISSION","SLIPPAGE","CASH_FLOW","FILL_PRICE","HIST_PRICE"]);
+        wrt.write_record(&header)?;
+        let mut flows = vec![0.;length];
+        let mut commissions = vec![0.;length];
+        let mut slippages = vec![0.;length];
+        //quantities and values of the fills of every bar, for their average price
+        let mut quantities = vec![0.;length];
+        let mut values = vec![0.;length];
+        let mut prices:Vec<Option<f64>> = vec![None;length];
+        for fill in self.fills.iter(){
+            flows[fill.bar] -= fill.quantity*fill.price;
+            commissions[fill.bar] += fill.commission;
+            slippages[fill.bar] += fill.slippage;
+            quantities[fill.bar] += fill.quantity.abs();
+            values[fill.bar] += fill.quantity.abs()*fill.price;
+            prices[fill.bar] = Some(fill.price);
+        }
+        let mut last_price = 0.;
+        for i in 0..length{
+            let mktvalue = self.position[i]*self.quotes.close[i];
+            if let Some(price) = prices[i] {last_price = price;}
+            let fill_price = prices[i].map(|price|if quantities[i] > 0. {values[i]/quantities[i]} else {price});
+            let mut row = vec![self.quotes.datetime[i].to_string()[0..10].to_string(), self.quotes.open[i].to_string(),
+                self.quotes.close[i].to_string(), self.strategy.choices[i].to_string().to_string()];
+            if indicators.is_empty() {row.push(String::new());}
+            row.extend(indicators.iter().map(|indicator|indicator.format(i)));
+            row.extend([self.account[i], self.position[i], mktvalue, mktvalue+self.account[i], flows[i], commissions[i], slippages[i], self.flows[i]].iter().map(|x|x.to_string()));
+            row.push(fill_price.map(|price|price.to_string()).unwrap_or_default());
+            row.push(last_price.to_string());
+            wrt.write_record(&row)?;
+        }
+        wrt.flush()?;
+        println!("Backtesting saved as = {:?}",env::current_dir()?.join(filename));
+        Ok(())
     }
 }
\ No newline at end of file
@@ -5,24 +5,29 @@ use crate::backtester::Backtest;
 use plotters::coord::types::RangedCoordf64;
 use plotters::style::full_palette::{GREEN_900, GREY, ORANGE};
 use crate::orders;
+use crate::errors::{BacktestError, Result};
+
+fn chart_error<E:std::fmt::Display>(e:E)->BacktestError{
+    BacktestError::Chart(e.to_string())
+}
 
 ///function used to plot data, indicators and equity
 ///modify Plot_Config to define different chart parameters or apply default
-pub fn plot(backtest:Backtest, config: PlotConfig) ->Result<(), Box<dyn std::error::Error>>{
+pub fn plot(backtest:Backtest, config: PlotConfig) ->Result<()>{
     let yahoo_datetimes:Vec<DateTime<FixedOffset>> = backtest.quotes().timestamps();
     let opens:Vec<f64> = backtest.quotes().open();
     let highs:Vec<f64> = backtest.quotes().high();
     let lows:Vec<f64> = backtest.quotes().low();
     let closes:Vec<f64> = backtest.quotes().close();
 
-    let path = env::current_dir()?.into_os_string().into_string().unwrap()+"\\"+"plot.png";
+    let path = env::current_dir()?.join("plot.png");
     let root = BitMapBackend::new(&path, (1024, 768)).into_drawing_area();
-    let _ = root.fill(&WHITE);
+    root.fill(&WHITE).map_err(chart_error)?;
 
     let (upper,lower) = root.split_vertically(512);
 
-    let min_lows = *lows.iter().min_by(|x,y|x.partial_cmp(y).unwrap()).unwrap()*0.995;
-    let max_highs = *highs.iter().max_by(|x,y|x.partial_cmp(y).unwrap()).unwrap()*1.005;
+    let min_lows = lows.iter().cloned().fold(f64::INFINITY, f64::min)*0.995;
+    let max_highs = highs.iter().cloned().fold(f64::NEG_INFINITY, f64::max)*1.005;
 
     let mut chart: ChartContext<BitMapBackend, Cartesian2d<RangedDateTime<DateTime<FixedOffset>>, RangedCoordf64>>;
 
@@ -32,26 +37,26 @@ pub fn plot(backtest:Backtest, config: PlotConfig) ->Result<(), Box<dyn std::err
             .caption("Chart ".to_owned() + backtest.quotes().ticker(), ("sans-serif", 30).into_font())
             .x_label_area_size(40)
             .y_label_area_size(40)
-            .build_cartesian_2d(yahoo_datetimes[0]..yahoo_datetimes[yahoo_datetimes.len() - 1], min_lows..max_highs).unwrap();
+            .build_cartesian_2d(yahoo_datetimes[0]..yahoo_datetimes[yahoo_datetimes.len() - 1], min_lows..max_highs).map_err(chart_error)?;
     } else {
         chart = ChartBuilder::on(&root)
             .margin(5)
             .caption("Chart ".to_owned() + backtest.quotes().ticker(), ("sans-serif", 30).into_font())
             .x_label_area_size(40)
             .y_label_area_size(40)
-            .build_cartesian_2d(yahoo_datetimes[0]..yahoo_datetimes[yahoo_datetimes.len() - 1], min_lows..max_highs).unwrap();
+            .build_cartesian_2d(yahoo_datetimes[0]..yahoo_datetimes[yahoo_datetimes.len() - 1], min_lows..max_highs).map_err(chart_error)?;
     };
 
-    chart.configure_mesh().x_label_formatter(&|dt|dt.format("%Y-%m-%d").to_string()).draw().unwrap();
+    chart.configure_mesh().x_label_formatter(&|dt|dt.format("%Y-%m-%d").to_string()).draw().map_err(chart_error)?;
 
     if config.display_indic ==true {
-        let Some(indicator) = backtest.strategy().indicator() else { todo!() };
+        let indicator = backtest.strategy().indicator().unwrap_or_default();
         let colors = vec![CYAN,ORANGE];
         let colors_iter = colors.iter().cycle();
         for (nr,color) in indicator.iter().zip(colors_iter) {
             let color_clone = color.clone();
-            let Some(index) = nr.iter().position(|&x| x != -1.0) else { todo!() };
-            let _ = chart.draw_series(LineSeries::new((index..closes.len()).map(|i| (yahoo_datetimes[i], nr[i])), color_clone)).unwrap().label("indic").legend(move |(x, y)| Circle::new((x, y), 5, color_clone.filled()));
+            if nr.first_valid().is_none() { continue };
+            let _ = chart.draw_series(LineSeries::new((0..closes.len()).filter_map(|i| nr.get(i).map(|x|(yahoo_datetimes[i], x))), color_clone)).map_err(chart_error)?.label("indic").legend(move |(x, y)| Circle::new((x, y), 5, color_clone.filled()));
         }
     }
 
@@ -65,10 +70,10 @@ pub fn plot(backtest:Backtest, config: PlotConfig) ->Result<(), Box<dyn std::err
     let x:Vec<CustomRow> = yahoo_datetimes.iter().zip(opens.iter()).zip(highs.iter()).zip(lows.iter()).zip(closes.iter())
         .map(|((((date,&open),&high),&low),&close)|CustomRow{date:*date,value1:open,value2:high,value3:low,value4:close }).collect();
 
-    let _ = chart.draw_series(
+    chart.draw_series(
         x.iter().map(|x| {
             CandleStick::new(x.date,x.value1, x.value2, x.value3, x.value4, GREEN.filled(), RED.filled(), 500/yahoo_datetimes.len() as u32)
-        }),);
+        }),).map_err(chart_error)?;
 
     //add marker and label
     if config.display_marker_label==true {
@@ -80,14 +85,14 @@ pub fn plot(backtest:Backtest, config: PlotConfig) ->Result<(), Box<dyn std::err
                 5, // Circle marker size
                 &RED, // Red color
                 &|c, _s, _st| {
-                    return EmptyElement::at(c) +
+                    EmptyElement::at(c) +
                         match z{
                             orders::Order::BUY=>Polygon::new(&[(0, 0), (6, 0), (3, -6)], GREEN_900),
                             orders::Order::SHORTSELL=>Polygon::new(&[(0, 0), (6, 0), (3, 6)], RED),
                             orders::Order::NULL=>Polygon::new(&[(0,0),(6,0)], GREY),
-                        };
+                        }
                 },
-            ))?;
+            )).map_err(chart_error)?;
             prev_order=*z;
             };
         }
@@ -97,36 +102,39 @@ pub fn plot(backtest:Backtest, config: PlotConfig) ->Result<(), Box<dyn std::err
         .border_style(&BLACK)
         .background_style(&YELLOW.mix(0.8))
         .draw()
-        .unwrap();
+        .map_err(chart_error)?;
 
     if config.display_networth {
-        let networth: Vec<f64> = closes.iter().zip(backtest.position().iter()).zip(backtest.account().iter()).map(|((&a, &b), &c)| a * b + c).collect();
-        let min_nw = *networth.iter().min_by(|x, y| x.partial_cmp(y).unwrap()).unwrap() - 5000.0;
-        let max_nw = *networth.iter().max_by(|x, y| x.partial_cmp(y).unwrap()).unwrap() + 5000.0;
+        let networth: Vec<f64> = backtest.networth();
+        //gains and losses are measured against the net contributions (deposits are not gains)
+        let baseline: Vec<f64> = backtest.contributions();
+        let scale = baseline.iter().fold(0., |scale:f64, value|scale.max(value.abs()));
+        let min_nw = networth.iter().chain(baseline.iter()).cloned().fold(f64::INFINITY, f64::min) - 0.05*scale;
+        let max_nw = networth.iter().chain(baseline.iter()).cloned().fold(f64::NEG_INFINITY, f64::max) + 0.05*scale;
 
         let mut chart_low = ChartBuilder::on(&lower)
             .margin(5)
             .caption("Net worth", ("sans-serif", 30).into_font())
             .x_label_area_size(40)
             .y_label_area_size(40)
-            .build_cartesian_2d(yahoo_datetimes[0]..yahoo_datetimes[yahoo_datetimes.len() - 1], min_nw..max_nw).unwrap();
-
-        chart_low.configure_mesh().x_label_formatter(&|dt| dt.format("%Y-%m-%d").to_string()).draw().unwrap();
+            .build_cartesian_2d(yahoo_datetimes[0]..yahoo_datetimes[yahoo_datetimes.len() - 1], min_nw..max_nw).map_err(chart_error)?;
 
-        chart_low.draw_series(LineSeries::new((0..networth.len()).map(|i| (yahoo_datetimes[i], networth[i])), &BLUE)).unwrap().label("networth");
+        chart_low.configure_mesh().x_label_formatter(&|dt| dt.format("%Y-%m-%d").to_string()).draw().map_err(chart_error)?;
 
-        // area fill (GREEN, RED)
-        let gains: Vec<f64> = networth.clone().iter().map(|value|if *value > networth[0] { *value } else { networth[0] }).collect();
-        let losses: Vec<f64> = networth.clone().iter().map(|value|if *value < networth[0] { *value } else { networth[0] }).collect();
+        chart_low.draw_series(LineSeries::new((0..networth.len()).map(|i| (yahoo_datetimes[i], networth[i])), &BLUE)).map_err(chart_error)?.label("networth");
+        chart_low.draw_series(LineSeries::new((0..baseline.len()).map(|i| (yahoo_datetimes[i], baseline[i])), &GREY)).map_err(chart_error)?.label("net contributions");
 
-        chart_low.draw_series(AreaSeries::new((0..yahoo_datetimes.len()).map(|i|(yahoo_datetimes[i], gains[i])),networth[0], &GREEN.mix(0.2))).unwrap();
-        chart_low.draw_series(AreaSeries::new((0..yahoo_datetimes.len()).map(|i|(yahoo_datetimes[i], losses[i])),networth[0], &RED.mix(0.2))).unwrap();
+        // area fill (GREEN, RED) between net worth and net contributions
+        let area = |bound:fn(f64,f64)->f64|(0..networth.len()).map(|i|(yahoo_datetimes[i], bound(networth[i], baseline[i])))
+            .chain((0..baseline.len()).rev().map(|i|(yahoo_datetimes[i], baseline[i]))).collect::<Vec<_>>();
+        chart_low.draw_series(std::iter::once(Polygon::new(area(f64::max), GREEN.mix(0.2)))).map_err(chart_error)?;
+        chart_low.draw_series(std::iter::once(Polygon::new(area(f64::min), RED.mix(0.2)))).map_err(chart_error)?;
 
         chart_low.configure_series_labels()
             .border_style(&BLACK)
             .background_style(&YELLOW.mix(0.8))
             .draw()
-            .unwrap();
+            .map_err(chart_error)?;
     }
     println!("Chart saved as = {:?}",path);
     Ok(())
@@ -1,20 +1,11 @@
-use std::env;
-use chrono::{DateTime, Duration, FixedOffset, TimeZone};
-use csv::{Writer};
-use yahoo_finance_api as yahoo;
-use yahoo_finance_api::{Quote};
-use tokio_test;
-use std::error::Error;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, TimeZone};
+use csv::{StringRecord, Writer};
 use serde::{Serialize, Serializer};
 use serde::ser::{SerializeSeq};
+use crate::errors::{BacktestError, Result};
+use crate::providers::{DataProvider, YahooProvider};
 
-fn download_data(ticker:&str, interval:&str, range:&str) ->Result<Vec<Quote>,Box<dyn Error>>{
-    let provider = yahoo::YahooConnector::new().unwrap();
-    let response = tokio_test::block_on(provider.get_quote_range(ticker, interval, range)).unwrap();
-    let quotes = response.quotes().unwrap();
-    return Ok(quotes);
-}
-///struct to contain all market data (ticker + OHLC)
+///struct to contain all market data (ticker + OHLC + volume)
 #[derive(Clone, Serialize)]
 pub struct Data{
 pub ticker:String,
@@ -24,9 +15,10 @@ pub open:Vec<f64>,
 pub high:Vec<f64>,
 pub low:Vec<f64>,
 pub close:Vec<f64>,
+pub volume:Vec<f64>,
 }
 /*
-fn serialize_datetime<S>(datetime: &DateTime<FixedOffset>, serializer: S) -> Result<S::Ok, S::Error>
+fn serialize_datetime<S>(datetime: &DateTime<FixedOffset>, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: Serializer,
 {
@@ -35,7 +27,86 @@ fn serialize_datetime<S>(datetime: &DateTime<FixedOffset>, serializer: S) -> Res
 }
 */
 
-fn serialize_datetime_vec<S>(datetimes: &Vec<DateTime<FixedOffset>>, serializer: S) -> Result<S::Ok, S::Error>
+///Identifies a csv column either by position (starting from 0) or by header name (case insensitive)
+#[derive(Clone, Debug)]
+pub enum Column{
+    Index(usize),
+    Name(String),
+}
+
+impl Column{
+    fn position(&self, headers:&Option<StringRecord>)->Result<usize>{
+        match self{
+            Column::Index(index)=>Ok(*index),
+            Column::Name(name)=>{
+                let Some(headers) = headers else { return Err(BacktestError::InvalidParameter(format!("column {} requested by name but file has no header",name))) };
+                headers.iter().position(|h|h.trim().eq_ignore_ascii_case(name)).ok_or(BacktestError::Parse(format!("column {} not found",name)))
+            }
+        }
+    }
+}
+
+///Describes the layout of a csv file to read with Data::load_with<BR>
+///Default matches the files written by Data::save (DATE,OPEN,HIGH,LOW,CLOSE,VOLUME with RFC3339 dates)
+#[derive(Clone, Debug)]
+pub struct LoadConfig{
+    pub delimiter:u8,
+    pub has_headers:bool,
+    pub date:Column,
+    pub open:Column,
+    pub high:Column,
+    pub low:Column,
+    pub close:Column,
+    ///missing volume fields are read as 0; a volume column requested by name which is not found gives 0 volumes
+    ///if the file has no other columns than the ones read (an error otherwise)
+    pub volume:Option<Column>,
+    ///when set, close is replaced by the adjusted close and open/high/low are rescaled by the same factor
+    pub adj_close:Option<Column>,
+    ///chrono format string (e.g. "%d/%m/%Y"); None reads dates with offset like "2024-12-02 14:30:00 +00:00" or plain "%Y-%m-%d"
+    pub date_format:Option<String>,
+    ///offset given to dates which don't carry one
+    pub timezone:FixedOffset,
+}
+
+impl Default for LoadConfig{
+    fn default() -> Self {
+        Self{
+            delimiter:b',',
+            has_headers:true,
+            date:Column::Index(0),
+            open:Column::Index(1),
+            high:Column::Index(2),
+            low:Column::Index(3),
+            close:Column::Index(4),
+            volume:Some(Column::Name("VOLUME".to_string())),
+            adj_close:None,
+            date_format:None,
+            timezone:FixedOffset::east_opt(0).unwrap(),
+        }
+    }
+}
+
+fn parse_datetime(value:&str, config:&LoadConfig)->Result<DateTime<FixedOffset>>{
+    let format = match &config.date_format{
+        Some(format)=>format.as_str(),
+        None=>{
+            if let Ok(datetime) = value.parse::<DateTime<FixedOffset>>(){
+                return Ok(datetime);
+            }
+            "%Y-%m-%d"
+        }
+    };
+    if let Ok(datetime) = DateTime::parse_from_str(value, format){
+        return Ok(datetime);
+    }
+    let naive = match NaiveDateTime::parse_from_str(value, format){
+        Ok(naive)=>naive,
+        Err(_)=>NaiveDate::parse_from_str(value, format)?.and_hms_opt(0,0,0).unwrap(),
+    };
+    config.timezone.from_local_datetime(&naive).single().ok_or(BacktestError::Parse(format!("ambiguous date {}",value)))
+}
+
+fn serialize_datetime_vec<S>(datetimes: &Vec<DateTime<FixedOffset>>, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: Serializer,
 {
@@ -47,59 +118,101 @@ fn serialize_datetime_vec<S>(datetimes: &Vec<DateTime<FixedOffset>>, serializer:
 }
 
 impl Data{
-    ///retrieve OHLC data from yahoo
-    pub fn new_from_yahoo(ticker:&str, interval:&str, range:&str) ->Result<Self, Box<dyn Error>>{
-        let quotes = download_data(&ticker,interval, range)?;
-        let timestamps:Vec<u64> = quotes.iter().map(|s|s.timestamp).collect();
-        let yahoo_datetimes: Vec<DateTime<FixedOffset>> = timestamps.iter().map(|&ts|{FixedOffset::east_opt(0).unwrap().timestamp_opt(ts as i64,0).unwrap()}).collect();
-        let opens:Vec<f64> = quotes.iter().map(|s|s.open).collect();
-        let highs:Vec<f64> = quotes.iter().map(|s|s.high).collect();
-        let lows:Vec<f64> = quotes.iter().map(|s|s.low).collect();
-        let closes:Vec<f64> = quotes.iter().map(|s|s.close).collect();
-        Ok(Data{
-            ticker:ticker.to_string(),
-            datetime:yahoo_datetimes,
-            open:opens,
-            high:highs,
-            low:lows,
-            close:closes,
-        })
+    ///retrieve OHLC data from yahoo<BR>
+    ///shortcut for YahooProvider.fetch(), see the providers module for offline alternatives
+    pub fn new_from_yahoo(ticker:&str, interval:&str, range:&str) ->Result<Self>{
+        YahooProvider.fetch(ticker, interval, range)
     }
-    pub fn save(&self, filename:&str)->Result<(), Box<dyn Error>>{
-        let mut wrt = Writer::from_path(filename).expect("invalid path");
+    pub fn save(&self, filename:&str)->Result<()>{
+        let mut wrt = Writer::from_path(filename)?;
         let dates_t:Vec<Vec<String>> = self.datetime.iter().map(|e|vec![e.to_string()]).collect();
         let open_t:Vec<Vec<String>> = self.open.iter().map(|e|vec![e.to_string()]).collect();
         let high_t:Vec<Vec<String>> = self.high.iter().map(|e|vec![e.to_string()]).collect();
         let low_t:Vec<Vec<String>> = self.low.iter().map(|e|vec![e.to_string()]).collect();
         let close_t:Vec<Vec<String>> = self.close.iter().map(|e|vec![e.to_string()]).collect();
-        wrt.serialize(("DATE","OPEN","HIGH","LOW","CLOSE")).expect("cannot write data");
-        for ((((date,open),high),low),close) in dates_t.iter().zip(open_t.iter()).zip(high_t.iter()).zip(low_t.iter()).zip(close_t.iter()){
-            wrt.serialize((date,open,high,low,close)).expect("cannot write data");
+        let volume_t:Vec<Vec<String>> = self.volume.iter().map(|e|vec![e.to_string()]).collect();
+        wrt.serialize(("DATE","OPEN","HIGH","LOW","CLOSE","VOLUME"))?;
+        for (((((date,open),high),low),close),volume) in dates_t.iter().zip(open_t.iter()).zip(high_t.iter()).zip(low_t.iter()).zip(close_t.iter()).zip(volume_t.iter()){
+            wrt.serialize((date,open,high,low,close,volume))?;
         }
-        wrt.flush().expect("cannot write file");
+        wrt.flush()?;
         Ok(())
     }
-    ///load data from csv OHLC (no volume) format at specified path
-    pub fn load(path:&str, ticker:&str)->Result<Self,Box<dyn Error>>{
-        let path2 = env::current_dir();
-        let mut rdr = csv::Reader::from_path(path).expect(&format!("couldn't read file in {:?}",path2));
+    ///load data from csv OHLC(V) format at specified path<BR>
+    ///volume is read from the column named VOLUME (case insensitive) when present, otherwise it is set to 0;
+    ///a file with other columns (e.g. Adj Close) but no VOLUME column is an error<BR>
+    ///use Data::load_with for files with a different layout
+    pub fn load(path:&str, ticker:&str)->Result<Self>{
+        Data::load_with(path, ticker, &LoadConfig::default())
+    }
+    ///load data from a csv file described by a LoadConfig (columns, date format, delimiter, ...)
+    pub fn load_with(path:&str, ticker:&str, config:&LoadConfig)->Result<Self>{
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(config.delimiter)
+            .has_headers(config.has_headers)
+            .flexible(true)
+            .from_path(path)?;
+        let headers = if config.has_headers {Some(rdr.headers()?.clone())} else {None};
+        let date_col = config.date.position(&headers)?;
+        let open_col = config.open.position(&headers)?;
+        let high_col = config.high.position(&headers)?;
+        let low_col = config.low.position(&headers)?;
+        let close_col = config.close.position(&headers)?;
+        let adj_close_col = match &config.adj_close{Some(column)=>Some(column.position(&headers)?),None=>None};
+        let volume_col = match (&config.volume,&headers){
+            (Some(column@Column::Name(_)),Some(header))=>match column.position(&headers){
+                Ok(col)=>Some(col),
+                //any other column could be the volume under another name
+                Err(e)=>{
+                    let mut read = vec![date_col,open_col,high_col,low_col,close_col];
+                    read.extend(adj_close_col);
+                    if header.iter().enumerate().any(|(col,_)|!read.contains(&col)) {return Err(e);}
+                    None
+                }
+            },
+            (Some(column),_)=>Some(column.position(&headers)?),
+            (None,_)=>None,
+        };
         let mut datetime= Vec::new();
         let mut open = Vec::new();
         let mut high = Vec::new();
         let mut low = Vec::new();
         let mut close = Vec::new();
-        for result in rdr.records(){
-            let record = result.expect("couldn't read data");
-            let dates:DateTime<FixedOffset> = record[0].parse().expect("couldn't read data");
-            let opens:f64 = record[1].parse().expect("couldn't read data");
-            let highs:f64 = record[2].parse().expect("couldn't read data");
-            let lows:f64 = record[3].parse().expect("couldn't read data");
-            let closes:f64 = record[4].parse().expect("couldn't read data");
+        let mut volume = Vec::new();
+        for (line,result) in rdr.records().enumerate(){
+            let record = result?;
+            let field = |col:usize|->Result<&str>{
+                record.get(col).map(|s|s.trim()).ok_or(BacktestError::Parse(format!("missing column {} at record {}",col,line+1)))
+            };
+            let number = |col:usize|->Result<f64>{
+                let value = field(col)?;
+                value.parse().map_err(|_|BacktestError::Parse(format!("couldn't read number {:?} at record {}",value,line+1)))
+            };
+            let dates = parse_datetime(field(date_col)?, config)
+                .map_err(|e|BacktestError::Parse(format!("couldn't read date at record {}: {}",line+1,e)))?;
+            let mut opens = number(open_col)?;
+            let mut highs = number(high_col)?;
+            let mut lows = number(low_col)?;
+            let mut closes = number(close_col)?;
+            //a missing (or empty) volume field is read as 0
+            let volumes = match volume_col{
+                Some(col) if record.get(col).is_some_and(|s|!s.trim().is_empty())=>number(col)?,
+                _=>0.,
+            };
+            if let Some(col) = adj_close_col{
+                let adj_close = number(col)?;
+                let factor = adj_close/closes;
+                opens *= factor;
+                highs *= factor;
+                lows *= factor;
+                closes = adj_close;
+            }
             datetime.push(dates);
             open.push(opens);
             high.push(highs);
             low.push(lows);
             close.push(closes);
+            volume.push(volumes);
         }
         Ok(Data{
             ticker:ticker.to_string(),
@@ -108,53 +221,82 @@ impl Data{
             high,
             low,
             close,
+            volume,
         })
     }
+    ///returns a new Data with the bars in [start, end), an error if the range is not within the bars
+    pub fn slice(&self, start:usize, end:usize)->Result<Data>{
+        if start > end || end > self.datetime.len(){
+            return Err(BacktestError::InvalidParameter(format!("invalid range {}..{} of {} bars",start,end,self.datetime.len())));
+        }
+        Ok(Data{
+            ticker:self.ticker.clone(),
+            datetime:self.datetime[start..end].to_vec(),
+            open:self.open[start..end].to_vec(),
+            high:self.high[start..end].to_vec(),
+            low:self.low[start..end].to_vec(),
+            close:self.close[start..end].to_vec(),
+            volume:self.volume[start..end].to_vec(),
+        })
+    }
+    ///number of bars in a year, inferred from the median spacing between timestamps:<BR>
+    ///252 for daily bars, 52 for weekly, 12 for monthly and 252 trading days of 6.5 hours for intraday bars
+    pub fn periods_per_year(&self)->f64{
+        let mut spacing:Vec<i64> = self.datetime.windows(2).map(|w|(w[1]-w[0]).num_seconds()).collect();
+        if spacing.is_empty() {return 252.;}
+        spacing.sort();
+        let median = spacing[spacing.len()/2] as f64;
+        let day = 86400.;
+        if median >= 25.*day {12.}
+        else if median >= 5.*day {52.}
+        else if median >= 0.8*day {252.}
+        else {252.*6.5*3600./median.max(1.)}
+    }
     pub fn ticker(&self)->&str{
-        return &*self.ticker;
+        &self.ticker
     }
     pub fn timestamps(&self)->Vec<DateTime<FixedOffset>>{
-        return self.datetime.clone();
+        self.datetime.clone()
     }
     pub fn open(&self)->Vec<f64>{
-        return self.open.clone();
+        self.open.clone()
     }
-    pub fn high(&self)->Vec<f64>{return self.high.clone();}
-    pub fn low(&self)->Vec<f64>{return self.low.clone();}
+    pub fn high(&self)->Vec<f64>{self.high.clone()}
+    pub fn low(&self)->Vec<f64>{self.low.clone()}
     pub fn close(&self)->Vec<f64>{
-        return self.close.clone();
+        self.close.clone()
     }
+    pub fn volume(&self)->Vec<f64>{self.volume.clone()}
 
-    pub fn ret(&self)->f64{
-        println!("{}",self.timestamps().first().unwrap());
-        println!("{}",self.timestamps().last().unwrap());
-        println!("{}",self.close.last().unwrap());
-        println!("{}",self.close.first().unwrap());
-        return (self.close.last().unwrap()/self.open.first().unwrap()-1.)*100.;
+    pub fn ret(&self)->Result<f64>{
+        let (Some(first_open),Some(last_close)) = (self.open.first(),self.close.last()) else { return Err(BacktestError::EmptyData) };
+        Ok((last_close/first_open-1.)*100.)
     }
-    pub fn ret_from_date(&self, start_date:DateTime<FixedOffset>)->f64{
-        let pos = self.datetime.iter().position(|&x|x.date_naive()>=start_date.date_naive()).unwrap();
-        return self.close[pos];
+    pub fn ret_from_date(&self, start_date:DateTime<FixedOffset>)->Result<f64>{
+        let pos = self.datetime.iter().position(|&x|x.date_naive()>=start_date.date_naive())
+            .ok_or(BacktestError::InvalidParameter(format!("no data from {}",start_date)))?;
+        Ok(self.close[pos])
     }
     ///give return on given period (accepts xd(-ays) or xw(-eeks) where x is an integer)
-    pub fn ret_from_period(&self,term:&[&str])->Vec<f64>{
+    pub fn ret_from_period(&self,term:&[&str])->Result<Vec<f64>>{
         let mut ret = Vec::new();
-        let last_date = self.datetime.last().unwrap();
+        let last_date = self.datetime.last().ok_or(BacktestError::EmptyData)?;
         //let term = term2.first().unwrap();
         for i in term.iter() {
+            let invalid = ||BacktestError::InvalidParameter(format!("invalid period {}",i));
             let sought_date = match i.chars().last() {
-                Some('w') => last_date.checked_sub_signed(Duration::weeks(i[..i.len() - 1].parse().unwrap())).unwrap(),
-                Some('d') => last_date.checked_sub_signed(Duration::days(i[..i.len() - 1].parse().unwrap())).unwrap(),
+                Some('w') => last_date.checked_sub_signed(Duration::weeks(i[..i.len() - 1].parse().map_err(|_|invalid())?)).ok_or_else(invalid)?,
+                Some('d') => last_date.checked_sub_signed(Duration::days(i[..i.len() - 1].parse().map_err(|_|invalid())?)).ok_or_else(invalid)?,
                 Some(_c) => *last_date,
                 None => *last_date,
             };
             //println!("looking for {:}", sought_date.date_naive());
-            let pos = self.datetime.iter().position(|&x| x.date_naive() >= sought_date.date_naive()).unwrap();
+            let pos = self.datetime.iter().position(|&x| x.date_naive() >= sought_date.date_naive()).ok_or_else(invalid)?;
             //println!("date found was {:} - {:}", self.datetime[pos].date_naive(), self.close[pos]);
             //println!("final valuation date {:} - {:}", self.datetime.last().unwrap(), self.close().last().unwrap());
-            ret.push((self.close.last().unwrap() / self.close[pos] - 1.) * 100.);
+            ret.push((self.close[self.close.len()-1] / self.close[pos] - 1.) * 100.);
         };
-        return ret;
+        Ok(ret)
     }
     ///helper function to show data (datetime - close)
     pub fn show(&self){
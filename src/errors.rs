@@ -1,3 +1,79 @@
-use std::fmt::Error;
+use std::fmt;
 
-pub type Result<T> = std::result::Result<T,Error>;
\ No newline at end of file
+///Errors returned by the library
+#[derive(Debug)]
+pub enum BacktestError{
+    ///file system error (e.g. file not found)
+    Io(std::io::Error),
+    ///csv reading or writing error
+    Csv(csv::Error),
+    ///a value (number, date, ...) couldn't be parsed
+    Parse(String),
+    ///a data provider couldn't return the requested data
+    Provider(String),
+    ///a function was called with a parameter outside its valid range
+    InvalidParameter(String),
+    ///the operation needs at least one bar of data
+    EmptyData,
+    ///two series which should be aligned have different lengths
+    LengthMismatch{expected:usize, found:usize},
+    ///chart couldn't be drawn
+    Chart(String),
+}
+
+pub type Result<T> = std::result::Result<T,BacktestError>;
+
+impl fmt::Display for BacktestError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self{
+            BacktestError::Io(e)=>write!(f,"io error: {}",e),
+            BacktestError::Csv(e)=>write!(f,"csv error: {}",e),
+            BacktestError::Parse(msg)=>write!(f,"parse error: {}",msg),
+            BacktestError::Provider(msg)=>write!(f,"data provider error: {}",msg),
+            BacktestError::InvalidParameter(msg)=>write!(f,"invalid parameter: {}",msg),
+            BacktestError::EmptyData=>write!(f,"empty data"),
+            BacktestError::LengthMismatch{expected,found}=>write!(f,"length mismatch: expected {} values, found {}",expected,found),
+            BacktestError::Chart(msg)=>write!(f,"chart error: {}",msg),
+        }
+    }
+}
+
+impl std::error::Error for BacktestError{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self{
+            BacktestError::Io(e)=>Some(e),
+            BacktestError::Csv(e)=>Some(e),
+            _=>None,
+        }
+    }
+}
+
+impl From<std::io::Error> for BacktestError{
+    fn from(e: std::io::Error) -> Self {
+        BacktestError::Io(e)
+    }
+}
+
+impl From<csv::Error> for BacktestError{
+    fn from(e: csv::Error) -> Self {
+        BacktestError::Csv(e)
+    }
+}
+
+impl From<std::num::ParseFloatError> for BacktestError{
+    fn from(e: std::num::ParseFloatError) -> Self {
+        BacktestError::Parse(e.to_string())
+    }
+}
+
+impl From<chrono::ParseError> for BacktestError{
+    fn from(e: chrono::ParseError) -> Self {
+        BacktestError::Parse(e.to_string())
+    }
+}
+
+impl From<yahoo_finance_api::YahooError> for BacktestError{
+    fn from(e: yahoo_finance_api::YahooError) -> Self {
+        BacktestError::Provider(e.to_string())
+    }
+}
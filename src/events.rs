@@ -0,0 +1,153 @@
+use std::cell::{Cell, RefCell};
+use chrono::{DateTime, FixedOffset};
+use crate::datas::Data;
+use crate::errors::{BacktestError, Result};
+use crate::orders::{OrderRequest, OrderType, TimeInForce};
+use crate::series::Series;
+use crate::stops::StopRules;
+use crate::strategies;
+
+///Event-driven strategy, run bar by bar by Backtest::event_driven().<BR>
+///on_bar is called once per bar, after the fills of the bar (before the fills at its close with FillTiming::SameClose):
+///the target weight it sets is executed according to the fill timing, its orders can be filled from the next bar
+pub trait Strategy{
+    fn name(&self)->String{
+        "event_driven".to_string()
+    }
+    ///called once before the first bar
+    fn on_start(&mut self){}
+    fn on_bar(&mut self, context:&Context);
+    ///called for every order executed by the engine, before the next on_bar
+    fn on_fill(&mut self, _fill:&Fill){}
+    ///protective exits checked by the engine (the ones of the BacktestConfig if None)
+    fn stops(&self)->Option<StopRules>{None}
+    ///indicator recorded in the Strategy of the Backtest (for plotting and CSV export)
+    fn indicator(&self)->Option<Vec<Series>>{None}
+}
+
+///An execution of the Backtest engine (an entry, an exit or a rebalancing)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fill{
+    pub bar:usize,
+    pub datetime:DateTime<FixedOffset>,
+    ///signed quantity (positive for buys)
+    pub quantity:f64,
+    ///fill price, including slippage
+    pub price:f64,
+    pub commission:f64,
+    ///cost of slippage (already included in price)
+    pub slippage:f64,
+}
+
+///What an event-driven Strategy sees on a bar (history up to the bar, position and account)
+///and the commands it can give (target weight and order requests).<BR>
+///An invalid command is ignored and makes Backtest::event_driven() return an InvalidParameter error
+pub struct Context<'a>{
+    quotes:&'a Data,
+    bar:usize,
+    position:f64,
+    cash:f64,
+    next_id:usize,
+    target:Cell<Option<f64>>,
+    requests:RefCell<Vec<OrderRequest>>,
+    ///first invalid command of the bar
+    error:RefCell<Option<String>>,
+}
+
+impl<'a> Context<'a>{
+    pub(crate) fn new(quotes:&'a Data, bar:usize, position:f64, cash:f64, next_id:usize)->Self{
+        Context{quotes, bar, position, cash, next_id, target:Cell::new(None), requests:RefCell::new(Vec::new()), error:RefCell::new(None)}
+    }
+    ///Returns the target weight set on the bar (if any) and the order requests submitted, an error if a command was invalid
+    pub(crate) fn commands(self)->Result<(Option<f64>,Vec<OrderRequest>)>{
+        match self.error.into_inner(){
+            Some(msg)=>Err(BacktestError::InvalidParameter(msg)),
+            None=>Ok((self.target.get(), self.requests.into_inner())),
+        }
+    }
+    ///Records an invalid command (keeping the first one) and returns it as an error
+    fn reject(&self, msg:String)->BacktestError{
+        self.error.borrow_mut().get_or_insert(msg.clone());
+        BacktestError::InvalidParameter(msg)
+    }
+    ///Returns the index of the current bar
+    pub fn bar(&self)->usize{self.bar}
+    pub fn datetime(&self)->DateTime<FixedOffset>{self.quotes.datetime[self.bar]}
+    pub fn ticker(&self)->&str{self.quotes.ticker()}
+    ///Returns timestamps up to the current bar (included), same for open(), high(), low(), close() and volume()
+    pub fn timestamps(&self)->&[DateTime<FixedOffset>]{&self.quotes.datetime[..=self.bar]}
+    pub fn open(&self)->&[f64]{&self.quotes.open[..=self.bar]}
+    pub fn high(&self)->&[f64]{&self.quotes.high[..=self.bar]}
+    pub fn low(&self)->&[f64]{&self.quotes.low[..=self.bar]}
+    pub fn close(&self)->&[f64]{&self.quotes.close[..=self.bar]}
+    pub fn volume(&self)->&[f64]{&self.quotes.volume[..=self.bar]}
+    ///Returns a copy of the quotes up to the current bar (included), e.g. to compute indicators
+    pub fn history(&self)->Data{
+        Data{
+            ticker:self.quotes.ticker.clone(),
+            datetime:self.timestamps().to_vec(),
+            open:self.open().to_vec(),
+            high:self.high().to_vec(),
+            low:self.low().to_vec(),
+            close:self.close().to_vec(),
+            volume:self.volume().to_vec(),
+        }
+    }
+    ///Returns the (signed) position
+    pub fn position(&self)->f64{self.position}
+    pub fn cash(&self)->f64{self.cash}
+    ///Returns cash plus the position valued at the close of the current bar
+    pub fn networth(&self)->f64{self.cash+self.position*self.quotes.close[self.bar]}
+    ///Sets the target weight of the position (see Strategy::from_weights()), kept until it is set again
+    ///A weight which is not finite (e.g. NaN) is invalid
+    pub fn target(&self, weight:f64){
+        if !weight.is_finite(){
+            self.reject(format!("target {} on bar {}",weight,self.bar));
+            return;
+        }
+        self.target.set(Some(weight));
+    }
+    ///Submits an order and returns its id (to be used as parent of linked orders)
+    pub fn order(&self, target:f64, order_type:OrderType, tif:TimeInForce)->Result<usize>{
+        self.submit(OrderRequest::new(self.bar, target, order_type, tif))
+    }
+    ///Submits an order request (its bar is set to the current bar) and returns its id.<BR>
+    ///As for the order requests of a Strategy, the target must be finite and the parent an order submitted before
+    pub fn submit(&self, request:OrderRequest)->Result<usize>{
+        let mut requests = self.requests.borrow_mut();
+        let id = self.next_id+requests.len();
+        if !request.target.is_finite(){
+            return Err(self.reject(format!("order request on bar {} with target {}",self.bar,request.target)));
+        }
+        if request.parent.is_some_and(|parent|parent >= id){
+            return Err(self.reject(format!("order request {} has an invalid parent",id)));
+        }
+        requests.push(OrderRequest{bar:self.bar, ..request});
+        Ok(id)
+    }
+}
+
+///A vectorized Strategy runs as an event-driven one: on every bar it sets the target weight of the bar
+///and submits the order requests of the bar
+impl Strategy for strategies::Strategy{
+    fn name(&self)->String{
+        self.name.clone()
+    }
+    fn stops(&self)->Option<StopRules>{
+        self.stops
+    }
+    fn indicator(&self)->Option<Vec<Series>>{
+        self.indicator.clone()
+    }
+    fn on_bar(&mut self, context:&Context){
+        let bar = context.bar();
+        context.target(self.weight(bar));
+        //ids are given in order of submission, i.e. by bar and then by index
+        let id = |index:usize|self.requests.iter().enumerate()
+            .filter(|(other,request)|(request.bar,*other) < (self.requests[index].bar,index)).count();
+        for request in self.requests.iter().filter(|request|request.bar == bar){
+            //an invalid request is recorded by the context
+            let _ = context.submit(OrderRequest{parent:request.parent.map(id), ..*request});
+        }
+    }
+}
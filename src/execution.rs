@@ -0,0 +1,71 @@
+use crate::datas::Data;
+
+///Define the slippage model: fills are moved against the order (buys higher, sells lower) from the reference price.<BR>
+///Default is no slippage
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Slippage{
+    #[default]
+    None,
+    ///fixed number of basis points of the price (e.g. 5. = 0.05%)
+    Bps(f64),
+    ///fixed number of ticks of the given size
+    Ticks{ticks:f64, tick_size:f64},
+    ///fraction of the high-low range of the bar of execution (e.g. 0.1)
+    RangeFraction(f64),
+    ///impact times the price times the order quantity as a fraction of the volume of the bar of execution
+    ///(participation is capped at 1, bars without volume are charged the full impact)
+    VolumeParticipation{impact:f64},
+}
+
+impl Slippage{
+    ///Returns the slippage per share (always positive) on an order of quantity shares at price on bar of quotes
+    pub fn per_share(&self, quantity:f64, price:f64, quotes:&Data, bar:usize)->f64{
+        if quantity == 0. {return 0.;}
+        match self{
+            Slippage::None=>0.,
+            Slippage::Bps(bps)=>price*bps/10000.,
+            Slippage::Ticks{ticks,tick_size}=>ticks*tick_size,
+            Slippage::RangeFraction(fraction)=>fraction*(quotes.high[bar]-quotes.low[bar]),
+            Slippage::VolumeParticipation{impact}=>{
+                let volume = quotes.volume[bar];
+                let participation = if volume > 0. {(quantity.abs()/volume).min(1.)} else {1.};
+                impact*price*participation
+            }
+        }
+    }
+    ///Returns the fill price of an order of (signed) quantity shares: above price for buys, below price for sells
+    pub fn fill_price(&self, quantity:f64, price:f64, quotes:&Data, bar:usize)->f64{
+        price + quantity.signum()*self.per_share(quantity, price, quotes, bar)
+    }
+}
+
+///Define when and at which price the choice of a bar is executed.<BR>
+///Default is the open of the next bar
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum FillTiming{
+    #[default]
+    NextOpen,
+    ///close of the bar of the choice (market on close)
+    SameClose,
+    NextClose,
+    ///average of open, high, low and close of the next bar (a proxy of the VWAP)
+    OhlcAverage,
+}
+
+impl FillTiming{
+    ///Returns the number of bars between a choice and its execution
+    pub fn lag(&self)->usize{
+        match self{
+            FillTiming::SameClose=>0,
+            _=>1,
+        }
+    }
+    ///Returns the reference price (before slippage) of an order executed on bar of quotes
+    pub fn price(&self, quotes:&Data, bar:usize)->f64{
+        match self{
+            FillTiming::NextOpen=>quotes.open[bar],
+            FillTiming::SameClose | FillTiming::NextClose=>quotes.close[bar],
+            FillTiming::OhlcAverage=>(quotes.open[bar]+quotes.high[bar]+quotes.low[bar]+quotes.close[bar])/4.,
+        }
+    }
+}
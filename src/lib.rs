@@ -1,11 +1,21 @@
 pub mod datas;
+pub mod providers;
 pub mod errors;
 pub mod charts;
 pub mod orders;
+pub mod trades;
 pub mod strategies;
+pub mod execution;
+pub mod sizing;
+pub mod stops;
 pub mod backtester;
+pub mod events;
+pub mod lookahead;
+pub mod portfolio;
+pub mod series;
 pub mod ta;
 pub mod report;
+pub mod metrics;
 pub mod utilities;
 
-pub use errors::{Result};
\ No newline at end of file
+pub use errors::{BacktestError, Result};
\ No newline at end of file
@@ -0,0 +1,38 @@
+use crate::datas::Data;
+use crate::errors::{BacktestError, Result};
+use crate::strategies::Strategy;
+
+///Result of check_lookahead(): the bars whose signal depends on later data
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LookAhead{
+    pub bars:Vec<usize>,
+}
+
+impl LookAhead{
+    ///Returns true if no signal depends on later data
+    pub fn is_causal(&self)->bool{
+        self.bars.is_empty()
+    }
+    ///Returns the first bar whose signal depends on later data
+    pub fn first(&self)->Option<usize>{
+        self.bars.first().copied()
+    }
+}
+
+///Checks a strategy builder for look-ahead bias: the builder is re-run on every prefix of quotes
+///(from min_bars bars, e.g. the longest indicator period) and the target weight of the last bar of the prefix
+///is compared with the one computed on the full data; bars where they differ are flagged.<BR>
+///e.g. check_lookahead(&quotes, 20, |q|sma_cross(q,5,20))
+pub fn check_lookahead<F:Fn(Data)->Result<Strategy>>(quotes:&Data, min_bars:usize, builder:F)->Result<LookAhead>{
+    let length = quotes.datetime.len();
+    if length == 0 {return Err(BacktestError::EmptyData);}
+    let full = builder(quotes.clone())?.weights();
+    if full.len() != length {return Err(BacktestError::LengthMismatch{expected:length, found:full.len()});}
+    let mut bars = Vec::new();
+    for end in min_bars.max(1)..=length{
+        let prefix = builder(quotes.slice(0, end)?)?.weights();
+        if prefix.len() != end {return Err(BacktestError::LengthMismatch{expected:end, found:prefix.len()});}
+        if prefix[end-1] != full[end-1] {bars.push(end-1);}
+    }
+    Ok(LookAhead{bars})
+}
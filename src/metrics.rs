@@ -0,0 +1,71 @@
+//!Performance and risk measures computed from an equity (net worth) series.
+//!Ratios assume a zero risk-free rate and return 0 when they are undefined (e.g. no volatility).
+
+///Returns the period-by-period simple returns of an equity series
+pub fn returns(equity:&[f64])->Vec<f64>{
+    equity.windows(2).map(|w|w[1]/w[0]-1.).collect()
+}
+
+fn mean(values:&[f64])->f64{
+    if values.is_empty() {return 0.;}
+    values.iter().sum::<f64>()/values.len() as f64
+}
+
+fn std_dev(values:&[f64])->f64{
+    if values.len() < 2 {return 0.;}
+    let mean = mean(values);
+    (values.iter().map(|v|(v-mean).powi(2)).sum::<f64>()/(values.len()-1) as f64).sqrt()
+}
+
+///Compound annual growth rate of an equity series spanning the given number of years
+pub fn cagr(equity:&[f64], years:f64)->f64{
+    match (equity.first(),equity.last()){
+        (Some(first),Some(last)) if years > 0. && *first > 0. => (last/first).powf(1./years)-1.,
+        _=>0.,
+    }
+}
+
+///Annualized standard deviation of returns
+pub fn volatility(returns:&[f64], periods_per_year:f64)->f64{
+    std_dev(returns)*periods_per_year.sqrt()
+}
+
+///Annualized mean return over annualized volatility
+pub fn sharpe(returns:&[f64], periods_per_year:f64)->f64{
+    let std = std_dev(returns);
+    if std == 0. {return 0.;}
+    mean(returns)/std*periods_per_year.sqrt()
+}
+
+///Annualized mean return over annualized downside deviation (returns below zero only)
+pub fn sortino(returns:&[f64], periods_per_year:f64)->f64{
+    if returns.is_empty() {return 0.;}
+    let downside = (returns.iter().map(|r|r.min(0.).powi(2)).sum::<f64>()/returns.len() as f64).sqrt();
+    if downside == 0. {return 0.;}
+    mean(returns)/downside*periods_per_year.sqrt()
+}
+
+///Returns the maximum drawdown (as a positive fraction of the previous peak)
+///and the longest drawdown duration (number of bars spent below a previous peak)
+pub fn max_drawdown(equity:&[f64])->(f64,usize){
+    let mut peak = f64::NEG_INFINITY;
+    let mut peak_index = 0;
+    let mut max_dd = 0f64;
+    let mut max_duration = 0;
+    for (i,&value) in equity.iter().enumerate(){
+        if value >= peak{
+            peak = value;
+            peak_index = i;
+        } else {
+            max_dd = max_dd.max(1.-value/peak);
+            max_duration = max_duration.max(i-peak_index);
+        }
+    }
+    (max_dd,max_duration)
+}
+
+///CAGR over maximum drawdown
+pub fn calmar(cagr:f64, max_drawdown:f64)->f64{
+    if max_drawdown == 0. {return 0.;}
+    cagr/max_drawdown
+}
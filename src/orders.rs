@@ -1,5 +1,7 @@
 use serde::Serialize;
+use crate::datas::Data;
 
+///Choice of a Strategy on a bar: long, short or out of the market
 #[derive(Copy, Clone, Debug, PartialEq, Serialize)]
 pub enum Order{
     BUY,
@@ -14,4 +16,102 @@ impl Order{
             Order::SHORTSELL=>"short",
         }
     }
-}
\ No newline at end of file
+    ///Returns the target weight of the choice: 1 for BUY, -1 for SHORTSELL, 0 for NULL
+    pub fn weight(&self)->f64{
+        match self{
+            Order::BUY=>1.,
+            Order::NULL=>0.,
+            Order::SHORTSELL=>-1.,
+        }
+    }
+    ///Returns the choice matching the side of a target weight
+    pub fn from_weight(weight:f64)->Self{
+        if weight > 0. {Order::BUY} else if weight < 0. {Order::SHORTSELL} else {Order::NULL}
+    }
+}
+
+///Type of an OrderRequest: how and at which price it is filled against the OHLC of a bar.<BR>
+///Gaps are filled at the open when it is better than (limit) or beyond (stop) the order price
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub enum OrderType{
+    ///filled at the open of the next bar
+    MarketOnOpen,
+    ///filled at the close of the next bar
+    MarketOnClose,
+    ///filled when the price reaches the limit or better (low for buys, high for sells)
+    Limit(f64),
+    ///filled when the price reaches the stop or beyond (high for buys, low for sells)
+    Stop(f64),
+    ///becomes a limit order once the stop is reached: if the price reached triggering it is beyond the limit,
+    ///it is filled at the limit only if the bar comes back to it
+    StopLimit{stop:f64, limit:f64},
+}
+
+///How long an OrderRequest stays pending
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub enum TimeInForce{
+    ///the next bar only
+    Day,
+    GoodTillCancelled,
+    ///the given number of bars after the submission
+    Bars(usize),
+}
+
+///An order submitted by a Strategy on a bar (see Strategy::with_requests()), filled by the Backtest engine from the next bar.<BR>
+///Buys (sells) are orders whose target is above (below) the current target weight of the position.<BR>
+///Orders can be linked: a child is submitted when its parent is filled (and cancelled with it, or when the position is
+///closed before the child is filled), filling an order cancels the other orders of its one-cancels-other group (see Strategy::with_bracket() and Strategy::with_oco())
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub struct OrderRequest{
+    ///bar on which the order is submitted
+    pub bar:usize,
+    ///target weight of the position once filled (e.g. 1 = all long, 0 = close, see Strategy::from_weights())
+    pub target:f64,
+    pub order_type:OrderType,
+    pub tif:TimeInForce,
+    ///index (in the requests of the Strategy) of the order whose fill submits this one
+    pub parent:Option<usize>,
+    ///one-cancels-other group
+    pub oco:Option<usize>,
+}
+
+impl OrderRequest{
+    pub fn new(bar:usize, target:f64, order_type:OrderType, tif:TimeInForce)->Self{
+        OrderRequest{bar, target, order_type, tif, parent:None, oco:None}
+    }
+    ///Returns true if the order can still be filled on bar
+    pub fn is_active(&self, bar:usize)->bool{
+        bar > self.bar && match self.tif{
+            TimeInForce::Day=>bar == self.bar+1,
+            TimeInForce::GoodTillCancelled=>true,
+            TimeInForce::Bars(n)=>bar <= self.bar+n,
+        }
+    }
+}
+
+impl OrderType{
+    ///Returns the fill price of a buy (or sell) order on bar of quotes, if it is filled.<BR>
+    ///triggered records whether the stop of a StopLimit has been reached on a previous bar
+    pub fn fill_price(&self, buy:bool, triggered:&mut bool, quotes:&Data, bar:usize)->Option<f64>{
+        let side = if buy {1.} else {-1.};
+        let open = quotes.open[bar];
+        //best price for the order (low for buys) and worst price (high for buys)
+        let (best,worst) = if buy {(quotes.low[bar],quotes.high[bar])} else {(quotes.high[bar],quotes.low[bar])};
+        let limit_fill = |limit:f64|if best*side <= limit*side {Some(if open*side <= limit*side {open} else {limit})} else {None};
+        match self{
+            OrderType::MarketOnOpen=>Some(open),
+            OrderType::MarketOnClose=>Some(quotes.close[bar]),
+            OrderType::Limit(limit)=>limit_fill(*limit),
+            OrderType::Stop(stop)=>if worst*side >= stop*side {Some(if open*side >= stop*side {open} else {*stop})} else {None},
+            OrderType::StopLimit{stop,limit}=>{
+                if *triggered {return limit_fill(*limit);}
+                if worst*side < stop*side {return None;}
+                *triggered = true;
+                let trigger = if open*side >= stop*side {open} else {*stop};
+                if trigger*side <= limit*side {Some(trigger)}
+                else if best*side <= limit*side {Some(*limit)}
+                else {None}
+            }
+        }
+    }
+}
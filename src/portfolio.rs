@@ -0,0 +1,130 @@
+use std::collections::BTreeSet;
+use chrono::{DateTime, FixedOffset};
+use crate::backtester::{Commission, MonthlyVolume};
+use crate::datas::Data;
+use crate::errors::{BacktestError, Result};
+use crate::strategies::Strategy;
+
+///Backtest of a universe of assets sharing one cash account.<BR>
+///Every asset has its own Strategy and a weight: when the target weight of the strategy changes
+///the asset is rebalanced to target weight times weight of the portfolio net worth (e.g. BUY to +weight, SHORTSELL to -weight, NULL closes the position).<BR>
+///To create a PortfolioBacktest use PortfolioBacktest::new()
+#[derive(Clone)]
+pub struct PortfolioBacktest{
+    quotes:Vec<Data>,
+    strategies:Vec<Strategy>,
+    weights:Vec<f64>,
+    positions:Vec<Vec<f64>>,
+    cash:Vec<f64>,
+    commission:Commission,
+}
+
+impl PortfolioBacktest{
+    ///Use to create and calculate a PortfolioBacktest instance<BR>
+    ///quotes must be aligned (same timestamps, see align()) and there must be one strategy and one weight per asset
+    pub fn new(quotes:Vec<Data>, strategies:Vec<Strategy>, weights:Vec<f64>, account:f64, commission:Commission)->Result<Self>{
+        let Some(first) = quotes.first() else { return Err(BacktestError::EmptyData) };
+        let length = first.datetime.len();
+        if length == 0 {return Err(BacktestError::EmptyData);}
+        if strategies.len() != quotes.len() {return Err(BacktestError::LengthMismatch{expected:quotes.len(), found:strategies.len()});}
+        if weights.len() != quotes.len() {return Err(BacktestError::LengthMismatch{expected:quotes.len(), found:weights.len()});}
+        for (data,strategy) in quotes.iter().zip(strategies.iter()){
+            if data.datetime != first.datetime {
+                return Err(BacktestError::InvalidParameter(format!("quotes of {} are not aligned with {}",data.ticker(),first.ticker())));
+            }
+            if strategy.choices.len() != length {return Err(BacktestError::LengthMismatch{expected:length, found:strategy.choices.len()});}
+            if let Some(weights) = &strategy.weights{
+                if weights.len() != length {return Err(BacktestError::LengthMismatch{expected:length, found:weights.len()});}
+            }
+        }
+        let mut _backtest = PortfolioBacktest{
+            positions:vec![vec![0.;length];quotes.len()],
+            cash:vec![account;length],
+            quotes,
+            strategies,
+            weights,
+            commission,
+        };
+        _backtest.calculate();
+        Ok(_backtest)
+    }
+    ///Returns quotes of every asset
+    pub fn quotes(&self)->&[Data]{&self.quotes}
+    ///Returns tickers of the universe
+    pub fn tickers(&self)->Vec<String>{self.quotes.iter().map(|q|q.ticker.clone()).collect()}
+    ///Returns strategies (one per asset)
+    pub fn strategies(&self)->&[Strategy]{&self.strategies}
+    ///Returns timeserie of (signed) positions of the asset at index asset
+    pub fn position(&self, asset:usize)->Vec<f64>{self.positions[asset].clone()}
+    ///Returns timeseries of (signed) positions, one per asset
+    pub fn positions(&self)->Vec<Vec<f64>>{self.positions.clone()}
+    ///Returns timeserie of market values of the asset at index asset (valued at close)
+    pub fn market_value(&self, asset:usize)->Vec<f64>{
+        self.positions[asset].iter().zip(self.quotes[asset].close.iter()).map(|(p,c)|p*c).collect()
+    }
+    ///Returns timeserie of the shared cash account
+    pub fn cash(&self)->Vec<f64>{self.cash.clone()}
+    ///Returns combined equity curve (cash + market value of all positions, valued at close)
+    pub fn networth(&self)->Vec<f64>{
+        (0..self.cash.len()).map(|i|self.cash[i]+(0..self.quotes.len()).map(|a|self.positions[a][i]*self.quotes[a].close[i]).sum::<f64>()).collect()
+    }
+    ///function which does the actual backtest: orders of bar i-1 are executed at open of bar i.<BR>
+    ///Positions being reduced are traded first so that their proceeds can fund the others
+    fn calculate(&mut self){
+        let assets = self.quotes.len();
+        let mut targets = vec![0.;assets];
+        let mut volume = MonthlyVolume::new();
+        for i in 1..self.cash.len(){
+            let mut cash = self.cash[i-1];
+            for a in 0..assets{
+                self.positions[a][i] = self.positions[a][i-1];
+            }
+            let new_targets:Vec<f64> = (0..assets).map(|a|self.strategies[a].weight(i-1)*self.weights[a]).collect();
+            let networth = cash + (0..assets).map(|a|self.positions[a][i]*self.quotes[a].open[i]).sum::<f64>();
+            let mut desired = self.positions.iter().map(|p|p[i]).collect::<Vec<f64>>();
+            for a in 0..assets{
+                if new_targets[a] != targets[a]{
+                    let price = self.quotes[a].open[i];
+                    let budget = new_targets[a].abs()*networth;
+                    desired[a] = new_targets[a].signum()*self.commission.affordable_quantity(budget, price, volume.at(&self.quotes[a].datetime[i]));
+                }
+            }
+            let reducing = |a:&usize|desired[*a].abs() < self.positions[*a][i].abs() || desired[*a].signum() != self.positions[*a][i].signum();
+            let order:Vec<usize> = (0..assets).filter(reducing).chain((0..assets).filter(|a|!reducing(a))).collect();
+            for a in order{
+                let delta = desired[a]-self.positions[a][i];
+                if delta != 0.{
+                    let price = self.quotes[a].open[i];
+                    cash -= delta*price + self.commission.fee(delta, price, volume.at(&self.quotes[a].datetime[i]));
+                    volume.add(delta, price);
+                    self.positions[a][i] = desired[a];
+                }
+            }
+            self.cash[i] = cash;
+            targets = new_targets;
+        }
+    }
+}
+
+///Aligns a universe of quotes on the timestamps common to all of them (other bars are dropped)
+pub fn align(universe:&[Data])->Result<Vec<Data>>{
+    let Some(first) = universe.first() else { return Err(BacktestError::EmptyData) };
+    let mut common:BTreeSet<DateTime<FixedOffset>> = first.datetime.iter().cloned().collect();
+    for data in universe.iter().skip(1){
+        let dates:BTreeSet<DateTime<FixedOffset>> = data.datetime.iter().cloned().collect();
+        common = common.intersection(&dates).cloned().collect();
+    }
+    if common.is_empty() {return Err(BacktestError::EmptyData);}
+    Ok(universe.iter().map(|data|{
+        let keep:Vec<usize> = (0..data.datetime.len()).filter(|&i|common.contains(&data.datetime[i])).collect();
+        Data{
+            ticker:data.ticker.clone(),
+            datetime:keep.iter().map(|&i|data.datetime[i]).collect(),
+            open:keep.iter().map(|&i|data.open[i]).collect(),
+            high:keep.iter().map(|&i|data.high[i]).collect(),
+            low:keep.iter().map(|&i|data.low[i]).collect(),
+            close:keep.iter().map(|&i|data.close[i]).collect(),
+            volume:keep.iter().map(|&i|data.volume[i]).collect(),
+        }
+    }).collect())
+}
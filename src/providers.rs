@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, Months, TimeZone};
+use yahoo_finance_api as yahoo;
+use crate::datas::{Data, LoadConfig};
+use crate::errors::{BacktestError, Result};
+
+///Source of market data.<BR>
+///Every implementation returns a Data for a ticker, bar interval (e.g. "1d") and range (e.g. "6mo"),
+///using the same conventions as yahoo finance
+pub trait DataProvider{
+    fn fetch(&self, ticker:&str, interval:&str, range:&str)->Result<Data>;
+}
+
+///Downloads data from yahoo finance (requires network access)
+#[derive(Clone, Copy, Default)]
+pub struct YahooProvider;
+
+impl DataProvider for YahooProvider{
+    fn fetch(&self, ticker:&str, interval:&str, range:&str)->Result<Data>{
+        let provider = yahoo::YahooConnector::new()?;
+        let response = tokio_test::block_on(provider.get_quote_range(ticker, interval, range))?;
+        let quotes = response.quotes()?;
+        let datetime:Vec<DateTime<FixedOffset>> = quotes.iter().map(|s|FixedOffset::east_opt(0).unwrap().timestamp_opt(s.timestamp as i64,0).unwrap()).collect();
+        Ok(Data{
+            ticker:ticker.to_string(),
+            datetime,
+            open:quotes.iter().map(|s|s.open).collect(),
+            high:quotes.iter().map(|s|s.high).collect(),
+            low:quotes.iter().map(|s|s.low).collect(),
+            close:quotes.iter().map(|s|s.close).collect(),
+            volume:quotes.iter().map(|s|s.volume as f64).collect(),
+        })
+    }
+}
+
+///Reads data from a directory containing one csv file per ticker (named "TICKER.csv", as written by Data::save)<BR>
+///The interval is not resampled: bars are returned at the frequency stored in the file
+#[derive(Clone)]
+pub struct CsvProvider{
+    pub directory:PathBuf,
+    pub config:LoadConfig,
+}
+
+impl CsvProvider{
+    pub fn new(directory:impl Into<PathBuf>)->Self{
+        CsvProvider{directory:directory.into(), config:LoadConfig::default()}
+    }
+    ///Use for files with a layout other than the one written by Data::save
+    pub fn with_config(directory:impl Into<PathBuf>, config:LoadConfig)->Self{
+        CsvProvider{directory:directory.into(), config}
+    }
+}
+
+impl DataProvider for CsvProvider{
+    fn fetch(&self, ticker:&str, _interval:&str, range:&str)->Result<Data>{
+        let path = self.directory.join(format!("{}.csv",ticker));
+        let data = Data::load_with(path_str(&path)?, ticker, &self.config)?;
+        filter_range(&data, range)
+    }
+}
+
+///Serves data kept in memory, useful for tests and for synthetic series
+#[derive(Clone, Default)]
+pub struct MemoryProvider{
+    data:HashMap<String, Data>,
+}
+
+impl MemoryProvider{
+    pub fn new()->Self{
+        Self::default()
+    }
+    ///Stores (or replaces) the series for its ticker
+    pub fn insert(&mut self, data:Data){
+        self.data.insert(data.ticker().to_string(), data);
+    }
+}
+
+impl DataProvider for MemoryProvider{
+    fn fetch(&self, ticker:&str, _interval:&str, range:&str)->Result<Data>{
+        let data = self.data.get(ticker).ok_or(BacktestError::Provider(format!("no data in memory for ticker {}",ticker)))?;
+        filter_range(data, range)
+    }
+}
+
+///Replays fixtures saved as "TICKER_INTERVAL_RANGE.csv" in a directory.<BR>
+///When created with ReplayProvider::recording, missing fixtures are fetched from the wrapped provider and saved for later runs
+pub struct ReplayProvider{
+    pub directory:PathBuf,
+    source:Option<Box<dyn DataProvider>>,
+}
+
+impl ReplayProvider{
+    ///Replay only: fetching a ticker without a fixture is an error
+    pub fn new(directory:impl Into<PathBuf>)->Self{
+        ReplayProvider{directory:directory.into(), source:None}
+    }
+    ///Replay existing fixtures and record missing ones from source
+    pub fn recording(directory:impl Into<PathBuf>, source:impl DataProvider + 'static)->Self{
+        ReplayProvider{directory:directory.into(), source:Some(Box::new(source))}
+    }
+    pub fn fixture_path(&self, ticker:&str, interval:&str, range:&str)->PathBuf{
+        self.directory.join(format!("{}_{}_{}.csv",ticker,interval,range))
+    }
+}
+
+impl DataProvider for ReplayProvider{
+    fn fetch(&self, ticker:&str, interval:&str, range:&str)->Result<Data>{
+        let path = self.fixture_path(ticker, interval, range);
+        if path.exists(){
+            return Data::load(path_str(&path)?, ticker);
+        }
+        match &self.source{
+            Some(source)=>{
+                let data = source.fetch(ticker, interval, range)?;
+                data.save(path_str(&path)?)?;
+                Ok(data)
+            }
+            None=>Err(BacktestError::Provider(format!("no fixture found in {:?}",path))),
+        }
+    }
+}
+
+///Keeps only the bars within range (e.g. "5d", "1wk", "6mo", "1y", "ytd", "max") counting back from the last bar
+fn filter_range(data:&Data, range:&str)->Result<Data>{
+    let Some(last) = data.datetime.last() else { return Ok(data.clone()) };
+    if range == "max"{
+        return Ok(data.clone());
+    }
+    let start = match range_start(*last, range){
+        Some(start)=>start,
+        None=>return Err(BacktestError::InvalidParameter(format!("invalid range {}",range))),
+    };
+    let first = data.datetime.iter().position(|&x|x>=start).unwrap_or(data.datetime.len());
+    data.slice(first, data.datetime.len())
+}
+
+fn path_str(path:&Path)->Result<&str>{
+    path.to_str().ok_or(BacktestError::InvalidParameter(format!("invalid path {:?}",path)))
+}
+
+fn range_start(last:DateTime<FixedOffset>, range:&str)->Option<DateTime<FixedOffset>>{
+    if range == "ytd"{
+        let first_day = last.date_naive().with_ordinal(1)?.and_hms_opt(0,0,0)?;
+        return last.timezone().from_local_datetime(&first_day).single();
+    }
+    let split = range.find(|c:char|!c.is_ascii_digit())?;
+    let amount:u32 = range[..split].parse().ok()?;
+    match &range[split..]{
+        "d"=>last.checked_sub_signed(Duration::days(amount as i64)),
+        "wk"|"w"=>last.checked_sub_signed(Duration::weeks(amount as i64)),
+        "mo"=>last.checked_sub_months(Months::new(amount)),
+        "y"=>last.checked_sub_months(Months::new(amount*12)),
+        _=>None,
+    }
+}
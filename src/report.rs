@@ -1,79 +1,147 @@
+use chrono::{DateTime, FixedOffset};
 use crate::backtester::Backtest;
-use crate::orders::Order::{BUY, SHORTSELL, NULL};
+use crate::errors::{BacktestError, Result};
+use crate::metrics;
+
+///Summary statistics of a Backtest (returned by Backtest::report()).<BR>
+///Returns and rates are fractions (0.05 = 5%)
+#[derive(Clone, Debug, PartialEq)]
+pub struct BacktestReport{
+    pub strategy:String,
+    pub ticker:String,
+    pub start:DateTime<FixedOffset>,
+    pub end:DateTime<FixedOffset>,
+    ///total return on the initial capital (plus deposits, minus withdrawals), 0 if everything was withdrawn
+    pub ret:f64,
+    ///share of bars with an open position
+    pub exposure:f64,
+    ///number of trades in the ledger (including a position still open at the end)
+    pub trades:usize,
+    pub win_rate:f64,
+    ///return of the best trade (0 if there are no trades)
+    pub best_trade:f64,
+    ///return of the worst trade (0 if there are no trades)
+    pub worst_trade:f64,
+    ///total commission paid on the trades in the ledger
+    pub commission:f64,
+    ///total slippage paid on the trades in the ledger
+    pub slippage:f64,
+    ///compound annual growth rate
+    pub cagr:f64,
+    ///annualized volatility of bar returns
+    pub volatility:f64,
+    pub sharpe:f64,
+    pub sortino:f64,
+    pub calmar:f64,
+    ///maximum drawdown as a positive fraction of the previous peak
+    pub max_drawdown:f64,
+    ///longest time under a previous peak, in bars
+    pub max_drawdown_duration:usize,
+}
+
+impl Backtest{
+    ///Returns the summary statistics of the backtest
+    pub fn report(&self)->BacktestReport{
+        let quotes = self.quotes();
+        let length = quotes.datetime.len();
+        let networth = self.networth();
+        let equity_final = networth[length-1];
+        let null_count = self.position().iter().filter(|&&num|num==0.).count();
+        let trades = self.trades();
+        let n_win_trades = trades.iter().filter(|t|t.is_win()).count();
+        let periods_per_year = quotes.periods_per_year();
+        //deposits and withdrawals are not performance: risk measures use the returns net of cash flows
+        let returns = self.returns()[1..].to_vec();
+        let equity_curve = self.equity_curve();
+        let years = (quotes.datetime[length-1]-quotes.datetime[0]).num_seconds() as f64/(365.25*86400.);
+        let cagr = metrics::cagr(&equity_curve, years);
+        let (max_drawdown,max_drawdown_duration) = metrics::max_drawdown(&equity_curve);
+        //0 once everything was withdrawn, as returns()
+        let contributions = self.net_contributions();
+        let ret = if contributions > 0. {(equity_final-contributions)/contributions} else {0.};
+        BacktestReport{
+            strategy:self.strategy().name().clone(),
+            ticker:quotes.ticker().to_string(),
+            start:quotes.datetime[0],
+            end:quotes.datetime[length-1],
+            ret,
+            exposure:1.-(null_count as f64)/(length as f64),
+            trades:trades.len(),
+            win_rate:if trades.is_empty() {0.} else {n_win_trades as f64/trades.len() as f64},
+            best_trade:trades.iter().map(|t|t.ret()).reduce(f64::max).unwrap_or(0.),
+            worst_trade:trades.iter().map(|t|t.ret()).reduce(f64::min).unwrap_or(0.),
+            commission:trades.iter().map(|t|t.commission).sum(),
+            slippage:trades.iter().map(|t|t.slippage).sum(),
+            cagr,
+            volatility:metrics::volatility(&returns, periods_per_year),
+            sharpe:metrics::sharpe(&returns, periods_per_year),
+            sortino:metrics::sortino(&returns, periods_per_year),
+            calmar:metrics::calmar(cagr, max_drawdown),
+            max_drawdown,
+            max_drawdown_duration,
+        }
+    }
+}
 
 pub trait BacktestNr {
-    fn uniquereport(&self);
+    fn uniquereport(&self)->Result<()>;
 }
 
 impl BacktestNr for Backtest{
-    fn uniquereport(&self) {
-        vec![self.clone()].uniquereport();
+    fn uniquereport(&self)->Result<()> {
+        vec![self.clone()].uniquereport()
     }
 }
 
 impl BacktestNr for Vec<Backtest>{
-    fn uniquereport(&self) {
-        println!("Backtesting period {:?} - {:?} - ticker = {}", self.first().unwrap().quotes().timestamps().first().unwrap().date_naive(),
-                 self.first().unwrap().quotes().timestamps().last().unwrap().date_naive(),self.first().unwrap().quotes().ticker());
-        print!("{}",format!("{:<width$}","Strategies",width=20));
-        print!("{}",format!("{:>width$}","Return",width=20));
-        print!("{}",format!("{:>width$}","Exposure Time %",width=20));
-        print!("{}",format!("{:>width$}","Trades #",width=20));
-        print!("{}",format!("{:>width$}","Win Rate [%]",width=20));
-        print!("{}",format!("{:>width$}","Best Trade [%]",width=20));
-        println!("{}",format!("{:>width$}","Worst Trade [%]",width=20));
+    fn uniquereport(&self)->Result<()> {
+        let first = self.first().ok_or(BacktestError::EmptyData)?.report();
+        println!("Backtesting period {:?} - {:?} - ticker = {}", first.start.date_naive(), first.end.date_naive(), first.ticker);
+        print!("{:<20}","Strategies");
+        print!("{:>20}","Return");
+        print!("{:>20}","Exposure Time %");
+        print!("{:>20}","Trades #");
+        print!("{:>20}","Win Rate [%]");
+        print!("{:>20}","Best Trade [%]");
+        print!("{:>20}","Worst Trade [%]");
+        print!("{:>20}","Commission");
+        println!("{:>20}","Slippage");
         for i in self.iter(){
-            let equity_final = i.position().last().unwrap()*i.quotes().close().last().unwrap()+i.account().last().unwrap();
-            let ret = (equity_final-100000.)/100000.;
-            let null_count = i.position().iter().filter(|&&num|num==0.).count();
-            let mut trade_count=0;
-            if i.strategy().choices[0]!=NULL {trade_count=1};
-            let mut profit=0f64;
-            let mut max_profit = 0f64;
-            let mut max_loss = 0f64;
-            let mut starting_value=i.quotes().open()[1];
-            let mut n_win_trades = 0;
-            for j in 1..i.strategy().choices().len()-1 {
-                if i.strategy().choices()[j] != i.strategy().choices()[j - 1] {
-                    if trade_count != 0 {
-                        if i.strategy().choices()[j - 1] == BUY { profit = i.quotes().open()[j + 1] * (1. - i.commission_rate()) / starting_value - 1. }
-                        else if i.strategy().choices()[j-1] == SHORTSELL{ profit = starting_value / (i.quotes().open()[j + 1] * (1. + i.commission_rate())) - 1. }
-                    };
-                    if i.strategy().choices()[j] == BUY {
-                        starting_value = i.quotes().open()[j + 1] * (1. + i.commission_rate())
-                    } else if i.strategy().choices()[j] == SHORTSELL {
-                        starting_value = i.quotes().open()[j + 1] * (1. - i.commission_rate())
-                    };
-                    max_profit = f64::max(max_profit, profit);
-                    max_loss = f64::min(max_loss, profit);
-                    if profit > 0. { n_win_trades = n_win_trades + 1;};
-                    if i.strategy().choices()[j] != NULL {
-                        trade_count = trade_count + 1;
-                    }
-                }
-            }
-            profit = 0.;
-            //implement force-close of an existing position
-            if *i.strategy().choices().iter().nth_back(1).unwrap()==BUY{profit = i.quotes().close().last().unwrap()*(1.-i.commission_rate())/starting_value-1.}
-            else if *i.strategy().choices().iter().nth_back(1).unwrap()==SHORTSELL{profit = starting_value/i.quotes().close().last().unwrap()*(1.+i.commission_rate())-1.}
-            max_profit = f64::max(max_profit, profit);
-            max_loss = f64::min(max_loss, profit);
-            if profit>0.{n_win_trades = n_win_trades+1};
-
-            //if last period has a choice but second-to-last hasn't, trade has still to happen
-            //if (*i.strategy().choices().iter().nth_back(1).unwrap()==NULL) & (*i.strategy().choices().iter().last().unwrap()!=NULL) {trade_count=trade_count-1;}
-
-            print!("{}",format!("{:<width$}", i.strategy().name(), width = 20));
-            print!("{}",format!("{:>width$}",format!("{:.2}%",ret*100.),width=20));
-            print!("{}",format!("{:>width$}",format!("{:.2}%",100.-(null_count as f64)/(i.quotes().timestamps().len() as f64)*100.),width=20));
-            print!("{}",format!("{:>width$}", trade_count, width = 20));
-            print!("{}",format!("{:>width$}",format!("{:.2}%",n_win_trades as f64/trade_count as f64 *100.),width=20));
-            print!("{}",format!("{:>width$}",format!("{:.2}%",max_profit*100.),width=20));
-            println!("{}",format!("{:>width$}",format!("{:.2}%",max_loss*100.),width=20));
+            let report = i.report();
+            print!("{:<20}",report.strategy);
+            print!("{:>19.2}%",report.ret*100.);
+            print!("{:>19.2}%",report.exposure*100.);
+            print!("{:>20}",report.trades);
+            print!("{:>19.2}%",report.win_rate*100.);
+            print!("{:>19.2}%",report.best_trade*100.);
+            print!("{:>19.2}%",report.worst_trade*100.);
+            print!("{:>20.2}",report.commission);
+            println!("{:>20.2}",report.slippage);
+        }
+        println!();
+        print!("{:<20}","Strategies");
+        print!("{:>20}","CAGR [%]");
+        print!("{:>20}","Volatility [%]");
+        print!("{:>20}","Sharpe");
+        print!("{:>20}","Sortino");
+        print!("{:>20}","Calmar");
+        print!("{:>20}","Max Drawdown [%]");
+        println!("{:>20}","Max DD Duration");
+        for i in self.iter(){
+            let report = i.report();
+            print!("{:<20}",report.strategy);
+            print!("{:>19.2}%",report.cagr*100.);
+            print!("{:>19.2}%",report.volatility*100.);
+            print!("{:>20.2}",report.sharpe);
+            print!("{:>20.2}",report.sortino);
+            print!("{:>20.2}",report.calmar);
+            print!("{:>19.2}%",-report.max_drawdown*100.);
+            println!("{:>20}",report.max_drawdown_duration);
         }
+        Ok(())
     }
 }
 
-pub fn report<T: BacktestNr>(items: T){
-    items.uniquereport();
+pub fn report<T: BacktestNr>(items: T)->Result<()>{
+    items.uniquereport()
 }
\ No newline at end of file
@@ -0,0 +1,47 @@
+use std::ops::Index;
+use serde::Serialize;
+
+///Values of an indicator, one per bar: None where the value is missing (e.g. during the warm-up of a moving average)<BR>
+///so that any value (including negative ones) is a valid value
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Series(pub Vec<Option<f64>>);
+
+impl Series{
+    ///Returns a Series of length missing values
+    pub fn missing(length:usize)->Self{
+        Series(vec![None;length])
+    }
+    ///Returns a Series of length values whose first warmup values are missing, followed by values
+    pub fn with_warmup(length:usize, values:Vec<f64>)->Self{
+        let warmup = length.saturating_sub(values.len());
+        Series(std::iter::repeat_n(None, warmup).chain(values.into_iter().map(Some)).take(length).collect())
+    }
+    pub fn len(&self)->usize{self.0.len()}
+    pub fn is_empty(&self)->bool{self.0.is_empty()}
+    ///Returns the value of bar i (None if missing or out of range)
+    pub fn get(&self, i:usize)->Option<f64>{self.0.get(i).copied().flatten()}
+    pub fn values(&self)->&[Option<f64>]{&self.0}
+    pub fn iter(&self)->impl Iterator<Item=Option<f64>>+'_{self.0.iter().copied()}
+    ///Returns the index of the first value which is not missing
+    pub fn first_valid(&self)->Option<usize>{self.0.iter().position(|x|x.is_some())}
+    ///Returns the values with NaN for missing values
+    pub fn to_vec(&self)->Vec<f64>{self.iter().map(|x|x.unwrap_or(f64::NAN)).collect()}
+    ///Returns the value of bar i as text, empty if missing (as written to CSV files)
+    pub fn format(&self, i:usize)->String{self.get(i).map(|x|x.to_string()).unwrap_or_default()}
+}
+
+impl Index<usize> for Series{
+    type Output = Option<f64>;
+    fn index(&self, i:usize)->&Option<f64>{&self.0[i]}
+}
+
+impl From<Vec<Option<f64>>> for Series{
+    fn from(values:Vec<Option<f64>>)->Self{Series(values)}
+}
+
+///NaN values are missing
+impl From<Vec<f64>> for Series{
+    fn from(values:Vec<f64>)->Self{
+        Series(values.into_iter().map(|x|if x.is_nan() {None} else {Some(x)}).collect())
+    }
+}
@@ -0,0 +1,146 @@
+use crate::backtester::Commission;
+use crate::datas::Data;
+use crate::metrics;
+use crate::trades::Trade;
+
+///How a quantity is brought to a multiple of the step of a QuantityRule
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Rounding{
+    #[default]
+    Down,
+    Nearest,
+    Up,
+}
+
+///Define the quantities that can be traded: a minimum quantity, a step (lot size) and a rounding policy.<BR>
+///Default is whole shares rounded down, i.e. the largest number of shares that can be paid for.<BR>
+///Rounding::Nearest and Rounding::Up may exceed the budget by less than one step (the excess is taken from the account)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct QuantityRule{
+    ///smaller quantities are not traded
+    pub min_quantity:f64,
+    ///quantities are multiples of step (0. allows any fraction)
+    pub step:f64,
+    pub rounding:Rounding,
+}
+
+impl Default for QuantityRule{
+    fn default() -> Self {
+        QuantityRule{min_quantity:0., step:1., rounding:Rounding::Down}
+    }
+}
+
+impl QuantityRule{
+    ///fractional shares (e.g. crypto or brokers selling fractions of ETFs)
+    pub fn fractional()->Self{
+        QuantityRule{min_quantity:0., step:0., rounding:Rounding::Down}
+    }
+    ///multiples of a lot (e.g. futures contracts or FX lots), at least one lot
+    pub fn lots(size:f64)->Self{
+        QuantityRule{min_quantity:size, step:size, rounding:Rounding::Down}
+    }
+    ///Returns quantity (positive) rounded to a multiple of step, or 0 if it is below the minimum quantity
+    pub fn round(&self, quantity:f64)->f64{
+        let rounded = if self.step > 0. {
+            //tolerance so that quantities computed as 99.99999999 lots are not rounded down to 99
+            let steps = quantity/self.step;
+            let steps = match self.rounding{
+                Rounding::Down=>(steps+1e-9).floor(),
+                Rounding::Nearest=>steps.round(),
+                Rounding::Up=>(steps-1e-9).ceil(),
+            };
+            steps*self.step
+        } else {quantity};
+        if rounded < self.min_quantity {0.} else {rounded}
+    }
+    ///Returns the quantity (positive, rounded with the rule) whose value plus commission fits in budget,
+    ///given the value already traded in the month (see Commission::fee())
+    pub fn affordable(&self, budget:f64, price:f64, commission:&Commission, monthly_volume:f64)->f64{
+        if budget <= 0. || price <= 0. {return 0.;}
+        let cost = |q:f64|q*price+commission.fee(q, price, monthly_volume);
+        let (mut low, mut high) = (0., budget/price);
+        if cost(high) <= budget {
+            low = high;
+        } else {
+            for _ in 0..100{
+                let mid = (low+high)/2.;
+                if cost(mid) <= budget {low = mid} else {high = mid}
+            }
+        }
+        let quantity = self.round(low);
+        if self.rounding == Rounding::Down && self.step > 0. && quantity > 0. && cost(quantity) > budget {
+            return self.round(quantity-self.step);
+        }
+        quantity
+    }
+}
+
+///Define the amount invested on every entry of a Backtest (long or short).<BR>
+///Measures of risk only use the bars before the bar of execution, the budget never exceeds net worth.<BR>
+///Default is AllIn (the whole net worth)
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum PositionSizer{
+    #[default]
+    AllIn,
+    ///fixed amount of cash
+    FixedCash(f64),
+    ///fraction of net worth (e.g. 0.5)
+    FixedFraction(f64),
+    ///net worth scaled by target over the annualized volatility of close returns in the last period bars
+    ///(e.g. target 0.1 for 10%); no position is opened before period bars of history are available
+    VolatilityTarget{target:f64, period:usize},
+    ///quantity losing risk (a fraction of net worth) if the price moves by multiple times the average true range
+    ///of the last period bars; no position is opened before period bars of history are available
+    AtrRisk{risk:f64, multiple:f64, period:usize},
+    ///fraction of the Kelly criterion estimated on the trades closed so far
+    ///(win rate - loss rate / (average win / average loss)), fallback fraction of net worth before min_trades trades
+    Kelly{fraction:f64, min_trades:usize, fallback:f64},
+}
+
+impl PositionSizer{
+    ///Returns the amount to invest on an entry executed on bar of quotes, given the net worth and the trades closed so far
+    pub fn budget(&self, networth:f64, quotes:&Data, bar:usize, trades:&[Trade])->f64{
+        if networth <= 0. {return 0.;}
+        let budget = match self{
+            PositionSizer::AllIn=>networth,
+            PositionSizer::FixedCash(cash)=>*cash,
+            PositionSizer::FixedFraction(fraction)=>fraction*networth,
+            PositionSizer::VolatilityTarget{target,period}=>{
+                if *period == 0 || bar < period+1 {return 0.;}
+                let returns = metrics::returns(&quotes.close[bar-period-1..bar]);
+                let volatility = metrics::volatility(&returns, quotes.periods_per_year());
+                if volatility == 0. {networth} else {target/volatility*networth}
+            }
+            PositionSizer::AtrRisk{risk,multiple,period}=>{
+                if *period == 0 || bar < period+1 {return 0.;}
+                let atr = average_true_range(quotes, bar, *period);
+                if atr == 0. {networth} else {risk*networth/(multiple*atr)*quotes.close[bar-1]}
+            }
+            PositionSizer::Kelly{fraction,min_trades,fallback}=>{
+                if trades.len() < *min_trades || trades.is_empty() {fallback*networth}
+                else {fraction*kelly(trades)*networth}
+            }
+        };
+        budget.clamp(0., networth)
+    }
+}
+
+///simple average of the true ranges of the period bars before bar
+pub(crate) fn average_true_range(quotes:&Data, bar:usize, period:usize)->f64{
+    (bar-period..bar).map(|j|{
+        let previous = quotes.close[j-1];
+        (quotes.high[j]-quotes.low[j]).max((quotes.high[j]-previous).abs()).max((quotes.low[j]-previous).abs())
+    }).sum::<f64>()/period as f64
+}
+
+///Kelly criterion of the returns of trades (0 without winning trades, win rate without losing trades)
+fn kelly(trades:&[Trade])->f64{
+    let wins:Vec<f64> = trades.iter().filter(|t|t.is_win()).map(|t|t.ret()).collect();
+    let losses:Vec<f64> = trades.iter().filter(|t|!t.is_win()).map(|t|-t.ret()).collect();
+    if wins.is_empty() {return 0.;}
+    let win_rate = wins.len() as f64/trades.len() as f64;
+    let average_loss = losses.iter().sum::<f64>()/losses.len().max(1) as f64;
+    if average_loss <= 0. {return win_rate;}
+    let payoff = wins.iter().sum::<f64>()/wins.len() as f64/average_loss;
+    win_rate-(1.-win_rate)/payoff
+}
@@ -0,0 +1,47 @@
+use serde::Serialize;
+use crate::datas::Data;
+use crate::sizing::average_true_range;
+
+///Distance of a protective exit from the entry price (or from the best price since entry for a trailing stop)
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub enum Stop{
+    ///fraction of the price (e.g. 0.05 = 5%)
+    Percent(f64),
+    ///price difference
+    Absolute(f64),
+    ///multiple of the average true range of the period bars before the entry
+    Atr{multiple:f64, period:usize},
+}
+
+impl Stop{
+    ///Returns the distance from price for an entry on bar of quotes (None if there is not enough history for the ATR)
+    pub fn distance(&self, price:f64, quotes:&Data, bar:usize)->Option<f64>{
+        match self{
+            Stop::Percent(fraction)=>Some(price*fraction),
+            Stop::Absolute(distance)=>Some(*distance),
+            Stop::Atr{multiple,period}=>{
+                if *period == 0 || bar < period+1 {return None;}
+                Some(multiple*average_true_range(quotes, bar, *period))
+            }
+        }
+    }
+}
+
+///Protective exits of a position, attached to a Strategy (see Strategy::with_stops()) or to a BacktestConfig.<BR>
+///Stops are checked from the bar after the entry against low and high: a level crossed by the open (gap) is filled at the open,
+///otherwise at the level; when a stop and a take profit are both crossed within a bar the stop is assumed to come first.<BR>
+///After a protective exit the strategy stays out of the market until its target weight changes
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize)]
+pub struct StopRules{
+    pub stop_loss:Option<Stop>,
+    pub take_profit:Option<Stop>,
+    ///follows the highest high (lowest low for shorts) since the entry, updated at the end of every bar
+    pub trailing_stop:Option<Stop>,
+}
+
+impl StopRules{
+    ///Returns true if no rule is set
+    pub fn is_empty(&self)->bool{
+        self.stop_loss.is_none() && self.take_profit.is_none() && self.trailing_stop.is_none()
+    }
+}
@@ -1,27 +1,87 @@
 use csv::Writer;
 use crate::datas::Data;
-use crate::orders::Order;
+use crate::orders::{Order, OrderRequest, OrderType, TimeInForce};
 use crate::orders::Order::{BUY,SHORTSELL,NULL};
-use std::error::Error;
-use crate::ta::{Indicator,sma,rsi};
+use crate::errors::{BacktestError, Result};
+use crate::series::Series;
+use crate::stops::StopRules;
+use crate::ta::{Indicator,MovingAverage,Source,sma,rsi,macd,stochastic};
 use serde::{Serialize};
 
 /// Struct to hold vector of choices and indicators<BR>
-/// There is no specific constructor<BR>
-/// Need to be created via a user-defined function which return a Strategy
-#[derive(Clone, Serialize)]
+/// Create it with Strategy::new() (choices) or Strategy::from_weights() (target weights),
+/// usually within a user-defined function which return a Strategy
+#[derive(Clone, Default, Serialize)]
 pub struct Strategy{
     pub name:String,
     pub choices:Vec<Order>,
-    pub indicator:Option<Vec<Vec<f64>>>,
+    ///indicator values (None where missing), plotted and exported with the Backtest
+    pub indicator:Option<Vec<Series>>,
+    ///signed target exposure per bar as a fraction of the position sizer budget (e.g. 0.5 = half long, -1 = all short, 2 = leveraged long);
+    ///if None, weights are given by choices (see Order::weight())
+    pub weights:Option<Vec<f64>>,
+    ///protective exits, used instead of the ones of the BacktestConfig if set
+    pub stops:Option<StopRules>,
+    ///orders executed by the Backtest engine in addition to the changes of choices (or weights)
+    pub requests:Vec<OrderRequest>,
 }
 
 impl Strategy{
+    ///Returns a Strategy going long (BUY), short (SHORTSELL) or out of the market (NULL) on every bar
+    pub fn new(name:&str, choices:Vec<Order>, indicator:Option<Vec<Series>>)->Self{
+        Strategy{name:name.to_string(), choices, indicator, weights:None, stops:None, requests:Vec::new()}
+    }
+    ///Returns a Strategy with a target weight on every bar (choices follow the side of the weights)
+    pub fn from_weights(name:&str, weights:Vec<f64>, indicator:Option<Vec<Series>>)->Self{
+        let choices = weights.iter().map(|&w|Order::from_weight(w)).collect();
+        Strategy{weights:Some(weights), ..Strategy::new(name, choices, indicator)}
+    }
+    ///Returns the Strategy with protective exits (stop loss, take profit, trailing stop)
+    pub fn with_stops(mut self, stops:StopRules)->Self{
+        self.stops = Some(stops);
+        self
+    }
+    ///Returns the Strategy with order requests (limit, stop, ... see OrderRequest)
+    pub fn with_requests(mut self, requests:Vec<OrderRequest>)->Self{
+        self.requests = requests;
+        self
+    }
+    ///Returns the Strategy with a bracket order: an entry to target (submitted on bar) and, once it is filled,
+    ///a stop and a limit order closing the position at stop_loss and take_profit (the first filled cancels the other)
+    pub fn with_bracket(mut self, bar:usize, target:f64, entry:OrderType, tif:TimeInForce, stop_loss:f64, take_profit:f64)->Self{
+        let parent = self.requests.len();
+        let oco = Some(self.next_oco());
+        self.requests.push(OrderRequest::new(bar, target, entry, tif));
+        for order_type in [OrderType::Stop(stop_loss),OrderType::Limit(take_profit)]{
+            self.requests.push(OrderRequest{parent:Some(parent), oco, ..OrderRequest::new(bar, 0., order_type, TimeInForce::GoodTillCancelled)});
+        }
+        self
+    }
+    ///Returns the Strategy with a one-cancels-other group of orders (the first filled cancels the others)
+    pub fn with_oco(mut self, requests:Vec<OrderRequest>)->Self{
+        let oco = Some(self.next_oco());
+        self.requests.extend(requests.into_iter().map(|request|OrderRequest{oco, ..request}));
+        self
+    }
+    fn next_oco(&self)->usize{
+        self.requests.iter().filter_map(|request|request.oco).max().map_or(0, |oco|oco+1)
+    }
     pub fn choices(&self)->Vec<Order>{
-        return self.choices.clone();
+        self.choices.clone()
+    }
+    pub fn name(&self)->&String{&self.name}
+    pub fn indicator(&self)->Option<Vec<Series>>{self.indicator.clone()}
+    ///Returns the target weight of bar i, 0 (out of the market) after the last bar
+    pub fn weight(&self, i:usize)->f64{
+        match &self.weights{
+            Some(weights)=>weights.get(i).copied().unwrap_or(0.),
+            None=>self.choices.get(i).map_or(0., |choice|choice.weight()),
+        }
+    }
+    ///Returns the target weights of every bar
+    pub fn weights(&self)->Vec<f64>{
+        (0..self.choices.len()).map(|i|self.weight(i)).collect()
     }
-    pub fn name(&self)->&String{ return &self.name;}
-    pub fn indicator(&self)->Option<Vec<Vec<f64>>>{ return self.indicator.clone();}
     pub fn invert(&self) ->Self{
         let length = self.choices.len();
         let mut inv_choices = self.choices.clone();
@@ -33,6 +93,9 @@ impl Strategy{
             name:self.name.clone()+"_inv",
             choices: inv_choices,
             indicator,
+            weights:self.weights.as_ref().map(|w|w.iter().map(|x|-x).collect()),
+            stops:self.stops,
+            requests:self.requests.iter().map(|r|OrderRequest{target:-r.target, ..*r}).collect(),
         }
     }
     pub fn long_only(&self) ->Self{
@@ -46,6 +109,9 @@ impl Strategy{
             name:self.name.clone()+"_long",
             choices: long_choices,
             indicator,
+            weights:self.weights.as_ref().map(|w|w.iter().map(|x|x.max(0.)).collect()),
+            stops:self.stops,
+            requests:self.requests.iter().map(|r|OrderRequest{target:r.target.max(0.), ..*r}).collect(),
         }
     }
     pub fn short_only(&self) ->Self{
@@ -59,9 +125,12 @@ impl Strategy{
             name:self.name.clone()+"_short",
             choices: short_choices,
             indicator,
+            weights:self.weights.as_ref().map(|w|w.iter().map(|x|x.min(0.)).collect()),
+            stops:self.stops,
+            requests:self.requests.iter().map(|r|OrderRequest{target:r.target.min(0.), ..*r}).collect(),
         }
     }
-    pub fn to_csv(&self, filename:&str)->Result<(),Box<dyn Error>>{
+    pub fn to_csv(&self, filename:&str)->Result<()>{
         let mut wrt = Writer::from_path(filename)?;
         let choices_transpose:Vec<Vec<String>>= self.choices.iter().map(|e|vec![e.clone().to_string().to_string()]).collect();
         wrt.serialize("choices")?;
@@ -76,102 +145,109 @@ impl Strategy{
 pub fn buy_n_hold(quotes:Data)->Strategy{
     let length = quotes.timestamps().len();
     let choices = vec![BUY;length];
-    let name = "buy_and_hold".to_string();
-    let indicator = Some(vec![vec![-1.;length]]);
-    Strategy{
-        name:name,
-        choices:choices,
-        indicator,
-    }
+    Strategy::new("buy_and_hold", choices, Some(vec![Series::missing(length)]))
 }
 ///Returns the opposite of a Buy and Hold Strategy:
 /// start by shortselling and keep the short position open to the end
 pub fn short_n_hold(quotes:Data)->Strategy{
     let length = quotes.timestamps().len();
     let choices = vec![SHORTSELL;length];
-    let name = "short and hold".to_string();
-    let indicator = Some(vec![vec![-1.;length]]);
-    Strategy{
-        name:name,
-        choices:choices,
-        indicator,
-    }
+    Strategy::new("short and hold", choices, Some(vec![Series::missing(length)]))
 }
 ///Returns a Strategy which does exactly nothing (i.e. always stays out of the market)
 pub fn do_nothing(quotes:Data)->Strategy{
     let length = quotes.timestamps().len();
     let choices = vec![NULL;length];
-    let name = "do nothing".to_string();
-    let indicator = Some(vec![vec![-1.;length]]);
-    Strategy{
-        name:name,
-        choices:choices,
-        indicator,
-    }
+    Strategy::new("do nothing", choices, Some(vec![Series::missing(length)]))
 }
 ///Returns a Simple Moving Average Strategy with a user specified time-period
-pub fn simple_sma(quotes:Data, period:usize) ->Strategy{
-    let sma = sma(&quotes,period);
+pub fn simple_sma(quotes:Data, period:usize) ->Result<Strategy>{
+    let sma = sma(&quotes,period)?;
     let indicator = Indicator{indicator:sma,quotes:quotes};
     let length = indicator.quotes.timestamps().len();
     let mut choices = vec![NULL;length];
     for i in 0..length{
-        if indicator.indicator[i]!=-1.{
-            if indicator.indicator[i]>=indicator.quotes.open()[i]{
+        if let Some(sma) = indicator.indicator[i]{
+            if sma>=indicator.quotes.open()[i]{
                 choices[i] = BUY;
-            }else if indicator.indicator[i]<indicator.quotes.open()[i]{
+            }else if sma<indicator.quotes.open()[i]{
                choices[i] = SHORTSELL}
         }
     }
     let name = format!("simple_sma_{}",period);
     let indicator = Some(vec![indicator.indicator()]);
-    Strategy{
-        name:name,
-        choices:choices,
-        indicator,
-    }
+    Ok(Strategy::new(&name, choices, indicator))
 }
 ///Returns a Simple Moving Average Crossing Strategy (i.e. goes long when SMA short crosses SMA long and shortsells otherwise)<BR>
 ///User can specify both time-periods (short and long, with short first)
-pub fn sma_cross(quotes:Data, short_period:usize, long_period:usize)->Strategy{
-    if short_period >= long_period {panic!("Error: short SMA parameter should be shorter than long SMA parameter");}
-    let sma_short = sma(&quotes, short_period);
-    let sma_long = sma(&quotes, long_period);
-    let ind_short = Indicator{indicator:sma_short,quotes:quotes.clone()};
-    let ind_long = Indicator{indicator:sma_long, quotes:quotes.clone()};
+pub fn sma_cross(quotes:Data, short_period:usize, long_period:usize)->Result<Strategy>{
+    ma_cross(quotes, MovingAverage::Simple, short_period, long_period, Source::Close)
+}
+///Returns an Exponential Moving Average Crossing Strategy on close prices (see sma_cross())
+pub fn ema_cross(quotes:Data, short_period:usize, long_period:usize)->Result<Strategy>{
+    ma_cross(quotes, MovingAverage::Exponential, short_period, long_period, Source::Close)
+}
+///Returns a Moving Average Crossing Strategy of any kind of moving average (see ta::MovingAverage) of source prices:
+///goes long when the short average is above the long one and shortsells otherwise
+pub fn ma_cross(quotes:Data, average:MovingAverage, short_period:usize, long_period:usize, source:Source)->Result<Strategy>{
+    if short_period >= long_period {return Err(BacktestError::InvalidParameter(format!("short {} parameter should be shorter than long {} parameter",average.name().to_uppercase(),average.name().to_uppercase())));}
+    let ma_short = average.calculate(&quotes, short_period, source)?;
+    let ma_long = average.calculate(&quotes, long_period, source)?;
+    let ind_short = Indicator{indicator:ma_short,quotes:quotes.clone()};
+    let ind_long = Indicator{indicator:ma_long, quotes:quotes.clone()};
     let length = ind_short.quotes().timestamps().len();
     let mut choices = vec![NULL;length];
     for i in 0..length{
-        if ind_long.indicator()[i]!=-1.{
-            if ind_short.indicator()[i]>ind_long.indicator()[i]{choices[i]=BUY}
+        if let (Some(short),Some(long)) = (ind_short.indicator[i],ind_long.indicator[i]){
+            if short>long{choices[i]=BUY}
             else {choices[i]=SHORTSELL};
         }
     }
-    let name=format!("sma_cross_{}_{}",short_period,long_period);
+    let name=format!("{}_cross_{}_{}",average.name(),short_period,long_period);
     let indicator = Some(vec![ind_short.indicator(),ind_long.indicator()]);
-    Strategy{
-        name:name,
-        choices:choices,
-        indicator:indicator,
-    }
+    Ok(Strategy::new(&name, choices, indicator))
 }
 ///Returns a Relative Strength Index Strategy (i.e. goes short if RSI > 70, long when RSI < 30, and stay out of market elsewhere)
-pub fn rsi_strategy(quotes:Data, period:usize)->Strategy{
-    let rsi = rsi(&quotes,period);
+pub fn rsi_strategy(quotes:Data, period:usize)->Result<Strategy>{
+    let rsi = rsi(&quotes,period)?;
     let indicator = Indicator{indicator:rsi,quotes};
     let length = indicator.quotes().timestamps().len();
     let mut choices = vec![NULL;length];
     for i in 0..length{
-        if indicator.indicator()[i]!=-1.{
-            if indicator.indicator()[i]>70.{choices[i]=SHORTSELL}
-            else if indicator.indicator()[i]<30. {choices[i]=BUY}
+        if let Some(rsi) = indicator.indicator[i]{
+            if rsi>70.{choices[i]=SHORTSELL}
+            else if rsi<30. {choices[i]=BUY}
         }
     }
     let name = format!("rsi_{}",period);
     let indicator=Some(vec![indicator.indicator()]);
-    Strategy{
-        name,
-        choices,
-        indicator,
+    Ok(Strategy::new(&name, choices, indicator))
+}
+///Returns a MACD Strategy (i.e. goes long when the MACD line of close prices is above its signal line and shortsells otherwise)<BR>
+///User can specify the fast, slow and signal periods (usually 12, 26 and 9)
+pub fn macd_cross(quotes:Data, fast:usize, slow:usize, signal:usize)->Result<Strategy>{
+    let macd = macd(&quotes, fast, slow, signal, Source::Close)?;
+    let length = quotes.timestamps().len();
+    let mut choices = vec![NULL;length];
+    for (i,choice) in choices.iter_mut().enumerate(){
+        if let (Some(line),Some(signal)) = (macd.line[i],macd.signal[i]){
+            *choice = if line>signal {BUY} else {SHORTSELL};
+        }
     }
-}
\ No newline at end of file
+    let name = format!("macd_cross_{}_{}_{}",fast,slow,signal);
+    Ok(Strategy::new(&name, choices, Some(macd.into())))
+}
+///Returns a Stochastic Oscillator Strategy (i.e. goes short if %K > 80, long when %K < 20, and stay out of market elsewhere)
+pub fn stochastic_strategy(quotes:Data, k_period:usize, k_smoothing:usize, d_period:usize)->Result<Strategy>{
+    let stochastic = stochastic(&quotes, k_period, k_smoothing, d_period)?;
+    let length = quotes.timestamps().len();
+    let mut choices = vec![NULL;length];
+    for (i,choice) in choices.iter_mut().enumerate(){
+        if let Some(k) = stochastic.k[i]{
+            if k>80.{*choice=SHORTSELL}
+            else if k<20. {*choice=BUY}
+        }
+    }
+    let name = format!("stochastic_{}_{}_{}",k_period,k_smoothing,d_period);
+    Ok(Strategy::new(&name, choices, Some(stochastic.into())))
+}
@@ -1,18 +1,20 @@
 use crate::datas::Data;
 use csv::Writer;
-use std::error::Error;
+use crate::errors::{BacktestError, Result};
+use crate::metrics;
+use crate::series::Series;
 
 ///container for checking calculation of indicator vs mktdata
 #[derive(Clone)]
 pub struct Indicator{
-    pub indicator:Vec<f64>,
+    pub indicator:Series,
     pub quotes:Data,
 }
 
 impl Indicator{
-    pub fn to_csv(&self, filename:&str)->Result<(), Box<dyn Error>>{
+    pub fn to_csv(&self, filename:&str)->Result<()>{
         let mut wrt = Writer::from_path(filename)?;
-        let transpose_indic:Vec<Vec<String>> = self.indicator.iter().map(|e|vec![e.clone().to_string()]).collect();
+        let transpose_indic:Vec<Vec<String>> = (0..self.indicator.len()).map(|i|vec![self.indicator.format(i)]).collect();
         let transpose_quote:Vec<Vec<String>> = self.quotes.close().iter().map(|e|vec![e.clone().to_string()]).collect();
         wrt.serialize(("close","indicator"))?;
         for (col1,col2) in transpose_quote.iter().zip(transpose_indic.iter()){
@@ -22,36 +24,456 @@ impl Indicator{
         Ok(())
     }
     pub fn quotes(&self)->Data{
-        return self.quotes.clone();
+        self.quotes.clone()
     }
-    pub fn indicator(&self)->Vec<f64>{
-        return self.indicator.clone();
+    pub fn indicator(&self)->Series{
+        self.indicator.clone()
     }
 }
 
-pub fn sma(quotes:&Data, period:usize)->Vec<f64>{
-    let mut indicator:Vec<f64> = vec![-1.;period-1];
-    let length = quotes.timestamps().len();
-    for i in period..length+1{
-        let slice = &quotes.close()[i-period..i];
-        let sum:f64 =Iterator::sum(slice.iter());
-        let sma = sum/(period as f64);
-        indicator.append(&mut vec![sma;1]);
+fn check_period(period:usize)->Result<()>{
+    if period == 0 {return Err(BacktestError::InvalidParameter("period should be greater than 0".to_string()));}
+    Ok(())
+}
+
+///Price of a bar used by an indicator
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Source{
+    Open,
+    High,
+    Low,
+    #[default]
+    Close,
+    ///(high+low+close)/3
+    Typical,
+}
+
+impl Source{
+    ///Returns the prices of every bar of quotes
+    pub fn values(&self, quotes:&Data)->Vec<f64>{
+        match self{
+            Source::Open=>quotes.open.clone(),
+            Source::High=>quotes.high.clone(),
+            Source::Low=>quotes.low.clone(),
+            Source::Close=>quotes.close.clone(),
+            Source::Typical=>(0..quotes.close.len()).map(|i|(quotes.high[i]+quotes.low[i]+quotes.close[i])/3.).collect(),
+        }
     }
-    return indicator;
 }
-pub fn rsi(quotes:&Data, period:usize)->Vec<f64>{
-    let mut indicator:Vec<f64> = vec![-1.;period-1];
+
+///Kind of moving average, to select one by parameter (e.g. in strategies::ma_cross())
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum MovingAverage{
+    #[default]
+    Simple,
+    Exponential,
+    Weighted,
+    DoubleExponential,
+    TripleExponential,
+    Hull,
+    ///Kaufman Adaptive Moving Average, with the periods of the fastest and slowest smoothing (usually 2 and 30)
+    Kaufman{fast:usize, slow:usize},
+    VolumeWeighted,
+}
+
+impl MovingAverage{
+    ///Returns the moving average of source prices of quotes over period bars
+    pub fn calculate(&self, quotes:&Data, period:usize, source:Source)->Result<Series>{
+        match self{
+            MovingAverage::Simple=>sma_of(quotes, period, source),
+            MovingAverage::Exponential=>ema(quotes, period, source),
+            MovingAverage::Weighted=>wma(quotes, period, source),
+            MovingAverage::DoubleExponential=>dema(quotes, period, source),
+            MovingAverage::TripleExponential=>tema(quotes, period, source),
+            MovingAverage::Hull=>hma(quotes, period, source),
+            MovingAverage::Kaufman{fast,slow}=>kama(quotes, period, *fast, *slow, source),
+            MovingAverage::VolumeWeighted=>vwma(quotes, period, source),
+        }
+    }
+    ///Returns the short name of the moving average (e.g. "ema")
+    pub fn name(&self)->&str{
+        match self{
+            MovingAverage::Simple=>"sma",
+            MovingAverage::Exponential=>"ema",
+            MovingAverage::Weighted=>"wma",
+            MovingAverage::DoubleExponential=>"dema",
+            MovingAverage::TripleExponential=>"tema",
+            MovingAverage::Hull=>"hma",
+            MovingAverage::Kaufman{..}=>"kama",
+            MovingAverage::VolumeWeighted=>"vwma",
+        }
+    }
+}
+
+///Simple Moving Average of close prices (missing for the first period-1 bars)
+pub fn sma(quotes:&Data, period:usize)->Result<Series>{
+    sma_of(quotes, period, Source::Close)
+}
+///Simple Moving Average of source prices (missing for the first period-1 bars)
+pub fn sma_of(quotes:&Data, period:usize, source:Source)->Result<Series>{
+    check_period(period)?;
+    let values = source.values(quotes);
+    Ok(Series::with_warmup(values.len(), simple(&values, period)))
+}
+///Exponential Moving Average (smoothing 2/(period+1)), started from the simple average of the first period bars
+pub fn ema(quotes:&Data, period:usize, source:Source)->Result<Series>{
+    check_period(period)?;
+    let values = source.values(quotes);
+    Ok(Series::with_warmup(values.len(), exponential(&values, period)))
+}
+///Weighted Moving Average (linear weights, period for the last bar down to 1 for the first of the window)
+pub fn wma(quotes:&Data, period:usize, source:Source)->Result<Series>{
+    check_period(period)?;
+    let values = source.values(quotes);
+    Ok(Series::with_warmup(values.len(), weighted(&values, period)))
+}
+///Double Exponential Moving Average: 2*EMA-EMA(EMA) (missing for the first 2*(period-1) bars)
+pub fn dema(quotes:&Data, period:usize, source:Source)->Result<Series>{
+    check_period(period)?;
+    let values = source.values(quotes);
+    let ema1 = exponential(&values, period);
+    let ema2 = exponential(&ema1, period);
+    let lag = ema1.len()-ema2.len().min(ema1.len());
+    let dema = ema2.iter().enumerate().map(|(i,e2)|2.*ema1[i+lag]-e2).collect();
+    Ok(Series::with_warmup(values.len(), dema))
+}
+///Triple Exponential Moving Average: 3*EMA-3*EMA(EMA)+EMA(EMA(EMA)) (missing for the first 3*(period-1) bars)
+pub fn tema(quotes:&Data, period:usize, source:Source)->Result<Series>{
+    check_period(period)?;
+    let values = source.values(quotes);
+    let ema1 = exponential(&values, period);
+    let ema2 = exponential(&ema1, period);
+    let ema3 = exponential(&ema2, period);
+    let (lag1, lag2) = (ema1.len()-ema3.len().min(ema1.len()), ema2.len()-ema3.len().min(ema2.len()));
+    let tema = ema3.iter().enumerate().map(|(i,e3)|3.*ema1[i+lag1]-3.*ema2[i+lag2]+e3).collect();
+    Ok(Series::with_warmup(values.len(), tema))
+}
+///Hull Moving Average: WMA over sqrt(period) bars of 2*WMA(period/2)-WMA(period)
+pub fn hma(quotes:&Data, period:usize, source:Source)->Result<Series>{
+    check_period(period)?;
+    let values = source.values(quotes);
+    let half = weighted(&values, (period/2).max(1));
+    let full = weighted(&values, period);
+    let lag = half.len()-full.len().min(half.len());
+    let difference:Vec<f64> = full.iter().enumerate().map(|(i,f)|2.*half[i+lag]-f).collect();
+    let hma = weighted(&difference, ((period as f64).sqrt() as usize).max(1));
+    Ok(Series::with_warmup(values.len(), hma))
+}
+///Kaufman Adaptive Moving Average: the smoothing goes from 2/(slow+1) to 2/(fast+1) with the efficiency ratio
+///(net change over the sum of absolute changes) of the last period bars (missing for the first period bars)
+pub fn kama(quotes:&Data, period:usize, fast:usize, slow:usize, source:Source)->Result<Series>{
+    check_period(period)?;
+    if fast == 0 || slow <= fast {return Err(BacktestError::InvalidParameter("KAMA fast period should be greater than 0 and shorter than slow period".to_string()));}
+    let values = source.values(quotes);
+    let (fastest, slowest) = (2./(fast as f64+1.), 2./(slow as f64+1.));
+    let mut kama = Vec::new();
+    if values.len() > period {
+        let mut last = values[period-1];
+        for i in period..values.len(){
+            let change = (values[i]-values[i-period]).abs();
+            let volatility:f64 = (i-period+1..=i).map(|j|(values[j]-values[j-1]).abs()).sum();
+            let efficiency = if volatility == 0. {0.} else {change/volatility};
+            let smoothing = (efficiency*(fastest-slowest)+slowest).powi(2);
+            last += smoothing*(values[i]-last);
+            kama.push(last);
+        }
+    }
+    Ok(Series::with_warmup(values.len(), kama))
+}
+///Volume Weighted Moving Average (missing if the volume of the window is 0)
+pub fn vwma(quotes:&Data, period:usize, source:Source)->Result<Series>{
+    check_period(period)?;
+    let values = source.values(quotes);
+    let vwma = values.windows(period).zip(quotes.volume.windows(period)).map(|(prices,volumes)|{
+        let volume:f64 = volumes.iter().sum();
+        let value:f64 = prices.iter().zip(volumes).map(|(price,volume)|price*volume).sum();
+        if volume > 0. {Some(value/volume)} else {None}
+    });
+    Ok(Series(std::iter::repeat_n(None, (period-1).min(values.len())).chain(vwma).collect()))
+}
+
+///averages of the windows of period values (one per window, i.e. from index period-1 of values)
+fn simple(values:&[f64], period:usize)->Vec<f64>{
+    values.windows(period).map(|window|window.iter().sum::<f64>()/period as f64).collect()
+}
+///exponential averages from index period-1 of values, started from the simple average of the first window
+fn exponential(values:&[f64], period:usize)->Vec<f64>{
+    if values.len() < period {return Vec::new();}
+    let alpha = 2./(period as f64+1.);
+    let mut last = values[..period].iter().sum::<f64>()/period as f64;
+    let mut ema = vec![last];
+    for value in &values[period..]{
+        last += alpha*(value-last);
+        ema.push(last);
+    }
+    ema
+}
+///highest values of the windows of period values
+fn highest(values:&[f64], period:usize)->Vec<f64>{
+    values.windows(period).map(|window|window.iter().cloned().fold(f64::NEG_INFINITY, f64::max)).collect()
+}
+///lowest values of the windows of period values
+fn lowest(values:&[f64], period:usize)->Vec<f64>{
+    values.windows(period).map(|window|window.iter().cloned().fold(f64::INFINITY, f64::min)).collect()
+}
+///averages of the windows of period values of series (missing if a value of the window is missing)
+fn simple_series(series:&Series, period:usize)->Series{
+    let averages = series.values().windows(period).map(|window|window.iter().copied().sum::<Option<f64>>().map(|sum|sum/period as f64));
+    Series(std::iter::repeat_n(None, (period-1).min(series.len())).chain(averages).collect())
+}
+///linearly weighted averages of the windows of period values
+fn weighted(values:&[f64], period:usize)->Vec<f64>{
+    let total = (period*(period+1)/2) as f64;
+    values.windows(period).map(|window|window.iter().enumerate().map(|(j,x)|(j+1) as f64*x).sum::<f64>()/total).collect()
+}
+///Calculation of the Relative Strength Index (see rsi_with())
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum RsiMethod{
+    ///close to close changes, averages of gains and losses with Wilder smoothing (started from their simple average
+    ///over the first period changes)
+    #[default]
+    Wilder,
+    ///close to close changes, simple averages of gains and losses over the last period changes
+    Cutler,
+    ///close-open differences of the last period bars, gains and losses averaged over the number of bars with a gain
+    ///(resp. a loss): the calculation of the first versions of ta::rsi()
+    OpenClose,
+}
+
+///Relative Strength Index of close prices with Wilder smoothing (missing for the first period bars)
+pub fn rsi(quotes:&Data, period:usize)->Result<Series>{
+    rsi_with(quotes, period, RsiMethod::Wilder)
+}
+///Relative Strength Index calculated with method: 100-100/(1+average gain/average loss),
+///100 if there is no loss and 50 if prices did not change (missing for the first period bars, period-1 with RsiMethod::OpenClose)
+pub fn rsi_with(quotes:&Data, period:usize, method:RsiMethod)->Result<Series>{
+    check_period(period)?;
     let length = quotes.timestamps().len();
-    let diff:&Vec<f64> = &quotes.close().iter().zip(quotes.open().iter()).map(|(a,b)|a-b).collect();
-    for i in period..length+1{
-        let slice = &diff[i-period..i];
-        let positive:Vec<f64> = slice.iter().cloned().filter(|&x|x>0.0).collect();
-        let negative:Vec<f64> = slice.iter().cloned().filter(|&x|x<0.0).collect();
-        let sum_pos:f64 = Iterator::sum(positive.iter());
-        let sum_neg:f64 = Iterator::sum(negative.iter());
-        let rsi = 100. * (sum_pos/(positive.len() as f64))/(sum_pos/(positive.len() as f64)-sum_neg/(negative.len() as f64));
-        indicator.append(&mut vec![rsi;1])
-    }
-    return indicator;
-}
\ No newline at end of file
+    let index = |gain:f64, loss:f64|{
+        if loss == 0. {if gain == 0. {50.} else {100.}} else {100.-100./(1.+gain/loss)}
+    };
+    let changes:Vec<f64> = quotes.close.windows(2).map(|w|w[1]-w[0]).collect();
+    let gains:Vec<f64> = changes.iter().map(|x|x.max(0.)).collect();
+    let losses:Vec<f64> = changes.iter().map(|x|(-x).max(0.)).collect();
+    let mut indicator:Vec<f64> = Vec::new();
+    match method{
+        RsiMethod::Wilder=>{
+            if changes.len() >= period {
+                let mut gain = gains[..period].iter().sum::<f64>()/period as f64;
+                let mut loss = losses[..period].iter().sum::<f64>()/period as f64;
+                indicator.push(index(gain, loss));
+                for i in period..changes.len(){
+                    gain = (gain*(period as f64-1.)+gains[i])/period as f64;
+                    loss = (loss*(period as f64-1.)+losses[i])/period as f64;
+                    indicator.push(index(gain, loss));
+                }
+            }
+        }
+        RsiMethod::Cutler=>{
+            indicator = simple(&gains, period).iter().zip(simple(&losses, period).iter()).map(|(&gain,&loss)|index(gain, loss)).collect();
+        }
+        RsiMethod::OpenClose=>{
+            let diff:&Vec<f64> = &quotes.close().iter().zip(quotes.open().iter()).map(|(a,b)|a-b).collect();
+            //averages over the number of bars with a gain (resp. a loss), 0 without such bars
+            let average = |values:Vec<f64>|if values.is_empty() {0.} else {values.iter().sum::<f64>()/values.len() as f64};
+            for i in period..length+1{
+                let slice = &diff[i-period..i];
+                let gain = average(slice.iter().cloned().filter(|&x|x>0.0).collect());
+                let loss = -average(slice.iter().cloned().filter(|&x|x<0.0).collect());
+                indicator.push(index(gain, loss));
+            }
+        }
+    }
+    Ok(Series::with_warmup(length, indicator))
+}
+///Stochastic RSI: Stochastic Oscillator of the (Wilder) RSI over rsi_period bars, i.e. %K = 100*(RSI-lowest RSI)/(highest RSI-lowest RSI)
+///over stoch_period bars, smoothed over k_smoothing bars, and %D its average over d_period bars (missing if the RSI did not change)
+pub fn stoch_rsi(quotes:&Data, rsi_period:usize, stoch_period:usize, k_smoothing:usize, d_period:usize)->Result<Stochastic>{
+    check_period(stoch_period)?;
+    check_period(k_smoothing)?;
+    check_period(d_period)?;
+    let rsi = rsi(quotes, rsi_period)?;
+    let raw = rsi.values().windows(stoch_period).map(|window|{
+        let values = window.iter().copied().collect::<Option<Vec<f64>>>()?;
+        let (high, low) = (highest(&values, stoch_period)[0], lowest(&values, stoch_period)[0]);
+        if high == low {None} else {Some(100.*(values[stoch_period-1]-low)/(high-low))}
+    });
+    let raw = Series(std::iter::repeat_n(None, (stoch_period-1).min(rsi.len())).chain(raw).collect());
+    let k = simple_series(&raw, k_smoothing);
+    let d = simple_series(&k, d_period);
+    Ok(Stochastic{k, d})
+}
+///Indicator drawn as three lines around prices (Bollinger Bands, Keltner and Donchian Channels)<BR>
+///Convert it with into() to fill Strategy::indicator (upper, middle, lower)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bands{
+    pub upper:Series,
+    pub middle:Series,
+    pub lower:Series,
+}
+
+impl From<Bands> for Vec<Series>{
+    fn from(bands:Bands)->Self{
+        vec![bands.upper, bands.middle, bands.lower]
+    }
+}
+
+///True Range: the largest of high-low, |high-previous close| and |low-previous close| (high-low on the first bar)
+pub fn true_range(quotes:&Data)->Series{
+    Series((0..quotes.close.len()).map(|i|{
+        let range = quotes.high[i]-quotes.low[i];
+        if i == 0 {return Some(range);}
+        let previous = quotes.close[i-1];
+        Some(range.max((quotes.high[i]-previous).abs()).max((quotes.low[i]-previous).abs()))
+    }).collect())
+}
+///Average True Range with Wilder smoothing: started from the average of the true ranges of bars 1 to period,
+///then ATR = (previous ATR*(period-1)+TR)/period (missing for the first period bars)
+pub fn atr(quotes:&Data, period:usize)->Result<Series>{
+    check_period(period)?;
+    let length = quotes.close.len();
+    let true_range = true_range(quotes).to_vec();
+    let mut atr = Vec::new();
+    if length > period {
+        let mut last = true_range[1..=period].iter().sum::<f64>()/period as f64;
+        atr.push(last);
+        for tr in &true_range[period+1..]{
+            last = (last*(period as f64-1.)+tr)/period as f64;
+            atr.push(last);
+        }
+    }
+    Ok(Series::with_warmup(length, atr))
+}
+///Bollinger Bands: simple moving average of source prices plus and minus multiple times their (population) standard deviation
+///over period bars
+pub fn bollinger(quotes:&Data, period:usize, multiple:f64, source:Source)->Result<Bands>{
+    check_period(period)?;
+    let values = source.values(quotes);
+    let middle = simple(&values, period);
+    let deviation:Vec<f64> = values.windows(period).zip(middle.iter())
+        .map(|(window,mean)|(window.iter().map(|x|(x-mean).powi(2)).sum::<f64>()/period as f64).sqrt()).collect();
+    let band = |sign:f64|Series::with_warmup(values.len(), middle.iter().zip(deviation.iter()).map(|(m,d)|m+sign*multiple*d).collect());
+    Ok(Bands{upper:band(1.), middle:Series::with_warmup(values.len(), middle.clone()), lower:band(-1.)})
+}
+///Keltner Channels: exponential moving average of close prices over period bars plus and minus multiple times
+///the ATR over atr_period bars
+pub fn keltner(quotes:&Data, period:usize, atr_period:usize, multiple:f64)->Result<Bands>{
+    let middle = ema(quotes, period, Source::Close)?;
+    let atr = atr(quotes, atr_period)?;
+    let band = |sign:f64|Series((0..middle.len()).map(|i|Some(middle.get(i)?+sign*multiple*atr.get(i)?)).collect());
+    Ok(Bands{upper:band(1.), lower:band(-1.), middle})
+}
+///Donchian Channels: highest high and lowest low of the last period bars (current bar included) and their average
+pub fn donchian(quotes:&Data, period:usize)->Result<Bands>{
+    check_period(period)?;
+    let length = quotes.close.len();
+    let upper = highest(&quotes.high, period);
+    let lower = lowest(&quotes.low, period);
+    let middle = upper.iter().zip(lower.iter()).map(|(u,l)|(u+l)/2.).collect();
+    Ok(Bands{upper:Series::with_warmup(length, upper), middle:Series::with_warmup(length, middle), lower:Series::with_warmup(length, lower)})
+}
+///Historical volatility: annualized standard deviation of the close to close returns of the last period bars
+///(see metrics::volatility(), missing for the first period bars)
+pub fn historical_volatility(quotes:&Data, period:usize)->Result<Series>{
+    if period < 2 {return Err(BacktestError::InvalidParameter("historical volatility period should be at least 2".to_string()));}
+    let periods_per_year = quotes.periods_per_year();
+    let volatility = quotes.close.windows(period+1).map(|window|metrics::volatility(&metrics::returns(window), periods_per_year)).collect();
+    Ok(Series::with_warmup(quotes.close.len(), volatility))
+}
+
+///MACD lines: MACD line, signal line and histogram (MACD line - signal line)<BR>
+///Convert it with into() to fill Strategy::indicator
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Macd{
+    pub line:Series,
+    pub signal:Series,
+    pub histogram:Series,
+}
+
+impl From<Macd> for Vec<Series>{
+    fn from(macd:Macd)->Self{
+        vec![macd.line, macd.signal, macd.histogram]
+    }
+}
+
+///Stochastic Oscillator lines: %K and its simple moving average %D
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stochastic{
+    pub k:Series,
+    pub d:Series,
+}
+
+impl From<Stochastic> for Vec<Series>{
+    fn from(stochastic:Stochastic)->Self{
+        vec![stochastic.k, stochastic.d]
+    }
+}
+
+///Moving Average Convergence Divergence: EMA over fast bars - EMA over slow bars of source prices (usually 12 and 26),
+///signal line is the EMA over signal bars of the MACD line (usually 9)
+pub fn macd(quotes:&Data, fast:usize, slow:usize, signal:usize, source:Source)->Result<Macd>{
+    check_period(fast)?;
+    check_period(signal)?;
+    if slow <= fast {return Err(BacktestError::InvalidParameter("MACD fast period should be shorter than slow period".to_string()));}
+    let values = source.values(quotes);
+    let (ema_fast, ema_slow) = (exponential(&values, fast), exponential(&values, slow));
+    let lag = ema_fast.len()-ema_slow.len().min(ema_fast.len());
+    let line:Vec<f64> = ema_slow.iter().enumerate().map(|(i,slow)|ema_fast[i+lag]-slow).collect();
+    let signal_line = exponential(&line, signal);
+    let lag = line.len()-signal_line.len().min(line.len());
+    let histogram = signal_line.iter().enumerate().map(|(i,signal)|line[i+lag]-signal).collect();
+    let length = values.len();
+    Ok(Macd{line:Series::with_warmup(length, line), signal:Series::with_warmup(length, signal_line), histogram:Series::with_warmup(length, histogram)})
+}
+///Stochastic Oscillator: %K = 100*(close-lowest low)/(highest high-lowest low) over k_period bars, smoothed by a
+///simple moving average over k_smoothing bars (1 for the fast stochastic, usually 3 for the slow one),
+///%D is the simple moving average over d_period bars of %K (missing if highest high = lowest low)
+pub fn stochastic(quotes:&Data, k_period:usize, k_smoothing:usize, d_period:usize)->Result<Stochastic>{
+    check_period(k_period)?;
+    check_period(k_smoothing)?;
+    check_period(d_period)?;
+    let (high, low) = (highest(&quotes.high, k_period), lowest(&quotes.low, k_period));
+    let raw = high.iter().zip(low.iter()).enumerate().map(|(i,(high,low))|{
+        if high == low {None} else {Some(100.*(quotes.close[i+k_period-1]-low)/(high-low))}
+    });
+    let raw = Series(std::iter::repeat_n(None, (k_period-1).min(quotes.close.len())).chain(raw).collect());
+    let k = simple_series(&raw, k_smoothing);
+    let d = simple_series(&k, d_period);
+    Ok(Stochastic{k, d})
+}
+///Williams %R: -100*(highest high-close)/(highest high-lowest low) over period bars, from -100 to 0
+///(missing if highest high = lowest low)
+pub fn williams_r(quotes:&Data, period:usize)->Result<Series>{
+    check_period(period)?;
+    let (high, low) = (highest(&quotes.high, period), lowest(&quotes.low, period));
+    let williams = high.iter().zip(low.iter()).enumerate().map(|(i,(high,low))|{
+        if high == low {None} else {Some(-100.*(high-quotes.close[i+period-1])/(high-low))}
+    });
+    Ok(Series(std::iter::repeat_n(None, (period-1).min(quotes.close.len())).chain(williams).collect()))
+}
+///Commodity Channel Index: (typical price-SMA of typical price)/(0.015*mean absolute deviation) over period bars
+///(missing if the mean deviation is 0)
+pub fn cci(quotes:&Data, period:usize)->Result<Series>{
+    check_period(period)?;
+    let typical = Source::Typical.values(quotes);
+    let cci = typical.windows(period).map(|window|{
+        let mean = window.iter().sum::<f64>()/period as f64;
+        let deviation = window.iter().map(|x|(x-mean).abs()).sum::<f64>()/period as f64;
+        if deviation == 0. {None} else {Some((window[period-1]-mean)/(0.015*deviation))}
+    });
+    Ok(Series(std::iter::repeat_n(None, (period-1).min(typical.len())).chain(cci).collect()))
+}
+///Rate of Change: 100*(price/price period bars before-1) (missing for the first period bars, or if the previous price is 0)
+pub fn roc(quotes:&Data, period:usize, source:Source)->Result<Series>{
+    check_period(period)?;
+    let values = source.values(quotes);
+    Ok(Series((0..values.len()).map(|i|{
+        if i < period || values[i-period] == 0. {None} else {Some(100.*(values[i]/values[i-period]-1.))}
+    }).collect()))
+}
+///Momentum: price-price period bars before (missing for the first period bars)
+pub fn momentum(quotes:&Data, period:usize, source:Source)->Result<Series>{
+    check_period(period)?;
+    let values = source.values(quotes);
+    Ok(Series((0..values.len()).map(|i|if i < period {None} else {Some(values[i]-values[i-period])}).collect()))
+}
@@ -0,0 +1,75 @@
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+
+///Direction of a trade
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub enum Side{
+    Long,
+    Short,
+}
+
+///Why a trade was closed
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub enum ExitReason{
+    ///the strategy changed its choice
+    Signal,
+    ///an order request of the strategy was filled
+    Order,
+    ///the position was still open at the end of the data and is valued at the last close
+    EndOfData,
+    StopLoss,
+    TakeProfit,
+    TrailingStop,
+}
+
+impl Side{
+    pub fn to_string(&self)->&str{
+        match self{
+            Side::Long=>"long",
+            Side::Short=>"short",
+        }
+    }
+}
+
+impl ExitReason{
+    pub fn to_string(&self)->&str{
+        match self{
+            ExitReason::Signal=>"signal",
+            ExitReason::Order=>"order",
+            ExitReason::EndOfData=>"end of data",
+            ExitReason::StopLoss=>"stop loss",
+            ExitReason::TakeProfit=>"take profit",
+            ExitReason::TrailingStop=>"trailing stop",
+        }
+    }
+}
+
+///A round trip recorded by the Backtest engine (see Backtest::trades())
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trade{
+    pub side:Side,
+    pub entry_time:DateTime<FixedOffset>,
+    pub exit_time:DateTime<FixedOffset>,
+    ///number of shares (always positive, see side for the direction)
+    pub quantity:f64,
+    pub entry_price:f64,
+    pub exit_price:f64,
+    ///commission paid on entry and exit
+    pub commission:f64,
+    ///slippage paid on entry and exit (already included in entry and exit prices)
+    pub slippage:f64,
+    ///profit and loss net of commission
+    pub pnl:f64,
+    pub bars_held:usize,
+    pub exit_reason:ExitReason,
+}
+
+impl Trade{
+    ///Returns the profit and loss net of commission as a fraction of the capital invested at entry
+    pub fn ret(&self)->f64{
+        self.pnl/(self.quantity*self.entry_price)
+    }
+    pub fn is_win(&self)->bool{
+        self.pnl > 0.
+    }
+}
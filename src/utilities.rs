@@ -1,17 +1,18 @@
 use csv::Writer;
-use std::error::Error;
 use std::fs::File;
 use crate::datas::Data;
 use crate::strategies::Strategy;
+use crate::trades::Trade;
+use crate::errors::Result;
 
 // Define a trait for custom data structures
 pub trait SerializeAsCsv {
-    fn to_csv(&self, writer: &mut Writer<std::fs::File>) -> Result<(), Box<dyn Error>>;
+    fn to_csv(&self, writer: &mut Writer<std::fs::File>) -> Result<()>;
 }
 
 
 // General function to serialize any type that implements SerializeAsCsv
-pub fn serialize_to_csv<T: SerializeAsCsv>(data: &T, file_path: &str) -> Result<(), Box<dyn Error>> {
+pub fn serialize_to_csv<T: SerializeAsCsv>(data: &T, file_path: &str) -> Result<()> {
     let mut wtr = Writer::from_path(file_path)?;
     data.to_csv(&mut wtr)?;
     wtr.flush()?;
@@ -19,8 +20,8 @@ pub fn serialize_to_csv<T: SerializeAsCsv>(data: &T, file_path: &str) -> Result<
 }
 
 impl SerializeAsCsv for Data {
-    fn to_csv(&self, writer: &mut Writer<File>) -> Result<(), Box<dyn Error>> {
-        writer.serialize(("ticker","date","open","high","low","close")).expect("couldn't write csv");
+    fn to_csv(&self, writer: &mut Writer<File>) -> Result<()> {
+        writer.serialize(("ticker","date","open","high","low","close","volume"))?;
         for i in 0..self.datetime.len() {
             writer.write_record(&[
                 self.ticker.clone(),
@@ -29,6 +30,7 @@ impl SerializeAsCsv for Data {
                 self.high[i].to_string(),
                 self.low[i].to_string(),
                 self.close[i].to_string(),
+                self.volume[i].to_string(),
             ])?;
         }
         Ok(())
@@ -36,7 +38,7 @@ impl SerializeAsCsv for Data {
 }
 
 impl SerializeAsCsv for Strategy {
-    fn to_csv(&self, writer: &mut Writer<File>) -> Result<(), Box<dyn Error>> {
+    fn to_csv(&self, writer: &mut Writer<File>) -> Result<()> {
         if let Some(indicators) = self.indicator.clone() {
             let mut header: Vec<String> = Vec::new();
             header.push("Name".to_string());
@@ -47,10 +49,34 @@ impl SerializeAsCsv for Strategy {
             writer.write_record(&header)?;
             for i in 0..self.choices.len() {
                 let mut row: Vec<String> = vec![self.name.to_string(),self.choices[i].to_string().into()];
-                row.extend(indicators.iter().map(|indicator|indicator[i].to_string()));
+                row.extend(indicators.iter().map(|indicator|indicator.format(i)));
                 writer.write_record(&row)?;
             }
         }
         Ok(())
     }
 }
+
+
+impl SerializeAsCsv for Vec<Trade> {
+    fn to_csv(&self, writer: &mut Writer<File>) -> Result<()> {
+        writer.write_record(["side","entry_time","exit_time","quantity","entry_price","exit_price","commission","slippage","pnl","return","bars_held","exit_reason"])?;
+        for trade in self.iter() {
+            writer.write_record(&[
+                trade.side.to_string().to_string(),
+                trade.entry_time.to_string(),
+                trade.exit_time.to_string(),
+                trade.quantity.to_string(),
+                trade.entry_price.to_string(),
+                trade.exit_price.to_string(),
+                trade.commission.to_string(),
+                trade.slippage.to_string(),
+                trade.pnl.to_string(),
+                trade.ret().to_string(),
+                trade.bars_held.to_string(),
+                trade.exit_reason.to_string().to_string(),
+            ])?;
+        }
+        Ok(())
+    }
+}
@@ -1,12 +1,231 @@
-use rs_backtester::backtester::{Backtest, Commission};
-use rs_backtester::datas::Data;
+use rs_backtester::backtester::{Backtest, BacktestConfig, Commission};
+use rs_backtester::execution::{FillTiming, Slippage};
+use rs_backtester::sizing::{QuantityRule, Rounding};
+use rs_backtester::providers::{DataProvider, ReplayProvider};
 use rs_backtester::strategies::buy_n_hold;
+use rs_backtester::BacktestError;
 use std::error::Error;
+mod common;
+use common::synthetic_quotes;
+use rs_backtester::orders::Order::{BUY, NULL, SHORTSELL};
+use rs_backtester::strategies::Strategy;
+use rs_backtester::trades::{ExitReason, Side};
+use rs_backtester::utilities::serialize_to_csv;
 
 #[test]
 fn init_backtester()->Result<(),Box<dyn Error>>{
-    let quotes = Data::new_from_yahoo("AAPL","1d","1mo")?;
+    let quotes = ReplayProvider::new("tests/fixtures").fetch("AAPL","1d","1mo")?;
     let strategy = buy_n_hold(quotes.clone());
-    Backtest::new(quotes.clone(), strategy.clone(), 100000f64, Commission::default());
+    Backtest::new(quotes.clone(), strategy.clone(), 100000f64, Commission::default())?;
     Ok(())
-}
\ No newline at end of file
+}
+#[test]
+fn length_mismatch_backtester()->Result<(),Box<dyn Error>>{
+    let quotes = ReplayProvider::new("tests/fixtures").fetch("AAPL","1d","1mo")?;
+    let strategy = buy_n_hold(quotes.slice(0,10)?);
+    let backtest = Backtest::new(quotes.clone(), strategy, 100000f64, Commission::default());
+    assert!(matches!(backtest, Err(BacktestError::LengthMismatch{expected:_, found:10})));
+    Ok(())
+}
+
+#[test]
+fn trade_ledger()->Result<(),Box<dyn Error>>{
+    let quotes = synthetic_quotes(vec![10.,10.,12.,11.,9.,9.]);
+    let strategy = Strategy::new("ledger", vec![BUY,BUY,SHORTSELL,SHORTSELL,NULL,NULL], None);
+    let backtest = Backtest::new(quotes, strategy, 1000., Commission::default())?;
+    let trades = backtest.trades();
+    assert_eq!(trades.len(),2);
+    assert_eq!((trades[0].side,trades[0].quantity,trades[0].entry_price,trades[0].exit_price),(Side::Long,100.,10.,11.));
+    assert_eq!((trades[0].pnl,trades[0].bars_held,trades[0].exit_reason),(100.,2,ExitReason::Signal));
+    assert_eq!((trades[1].side,trades[1].quantity,trades[1].pnl),(Side::Short,100.,200.));
+    assert_eq!(backtest.account().last(),Some(&1300.));
+    let path = std::env::temp_dir().join("trades.csv");
+    serialize_to_csv(&trades, path.to_str().unwrap())?;
+    assert_eq!(std::fs::read_to_string(path)?.lines().count(),3);
+    Ok(())
+}
+
+#[test]
+fn commission_models()->Result<(),Box<dyn Error>>{
+    assert_eq!(Commission::Rate(0.01).fee(-100.,10.,0.),10.);
+    let per_share = Commission::PerShare{fee:0.01, min:1., max:Some(5.)};
+    assert_eq!((per_share.fee(50.,10.,0.),per_share.fee(300.,10.,0.),per_share.fee(1000.,10.,0.)),(1.,3.,5.));
+    //0.005 per share, at least 1 and at most 1% of the value traded
+    let capped = Commission::PerShareCapped{fee:0.005, min:1., max_rate:0.01};
+    assert_eq!((capped.fee(100.,50.,0.),capped.fee(1000.,50.,0.),capped.fee(-1000.,0.2,0.)),(1.,5.,2.));
+    assert_eq!(capped.fee(50.,1.,0.),0.5);
+    assert_eq!(Commission::PerTicket(2.).fee(0.,10.,0.),0.);
+    let tiered = Commission::Tiered(vec![(0.,0.01),(1000.,0.005)]);
+    assert_eq!((tiered.fee(10.,10.,999.),tiered.fee(10.,10.,1000.)),(1.,0.5));
+    assert_eq!(Commission::Rate(0.01).affordable_quantity(1010.,10.,0.),100.);
+
+    let quotes = synthetic_quotes(vec![10.,10.,12.,11.,9.,9.]);
+    let strategy = Strategy::new("ledger", vec![BUY,BUY,NULL,NULL,NULL,NULL], None);
+    let backtest = Backtest::new(quotes, strategy, 1000., Commission::PerTicket(5.))?;
+    let trades = backtest.trades();
+    assert_eq!((trades[0].quantity,trades[0].commission,trades[0].pnl),(99.,10.,99.-10.));
+    assert_eq!(backtest.account().last(),Some(&(1000.+99.-10.)));
+    Ok(())
+}
+
+#[test]
+fn slippage_models()->Result<(),Box<dyn Error>>{
+    let quotes = synthetic_quotes(vec![10.,10.,12.,11.,9.,9.]);
+    assert_eq!(Slippage::None.per_share(100.,10.,&quotes,1),0.);
+    assert!((Slippage::Bps(100.).per_share(100.,10.,&quotes,1)-0.1).abs()<1e-12);
+    assert!((Slippage::Ticks{ticks:2., tick_size:0.01}.per_share(-100.,10.,&quotes,1)-0.02).abs()<1e-12);
+    assert_eq!(Slippage::RangeFraction(0.25).per_share(100.,10.,&quotes,1),0.5);
+    assert_eq!(Slippage::VolumeParticipation{impact:0.1}.per_share(500.,10.,&quotes,1),0.5);
+    assert_eq!(Slippage::RangeFraction(0.25).fill_price(-100.,10.,&quotes,1),9.5);
+
+    let strategy = Strategy::new("slippage", vec![BUY,BUY,NULL,NULL,NULL,NULL], None);
+    let config = BacktestConfig{slippage:Slippage::RangeFraction(0.05), ..Default::default()};
+    let backtest = Backtest::with_config(quotes, strategy, 1000., config)?;
+    let trades = backtest.trades();
+    assert_eq!(trades[0].quantity,99.);
+    assert!((trades[0].entry_price-10.1).abs()<1e-12 && (trades[0].exit_price-10.9).abs()<1e-12);
+    assert!((trades[0].slippage-19.8).abs()<1e-9);
+    assert!((trades[0].pnl-99.*0.8).abs()<1e-9);
+    assert!((backtest.account().last().unwrap()-(1000.+99.*0.8)).abs()<1e-9);
+    assert!((backtest.report().slippage-19.8).abs()<1e-9);
+    Ok(())
+}
+
+#[test]
+fn fill_timing()->Result<(),Box<dyn Error>>{
+    let mut quotes = synthetic_quotes(vec![10.,10.,12.,11.,9.,9.]);
+    quotes.close = vec![11.,12.,13.,14.,15.,16.];
+    let strategy = Strategy::new("timing", vec![BUY,BUY,NULL,NULL,NULL,NULL], None);
+    let expected = [
+        (FillTiming::NextOpen,1,120.,10.,11.),
+        (FillTiming::SameClose,0,109.,11.,13.),
+        (FillTiming::NextClose,1,100.,12.,14.),
+        (FillTiming::OhlcAverage,1,114.,10.5,11.75),
+    ];
+    for (fill_timing,entry_bar,quantity,entry_price,exit_price) in expected{
+        let config = BacktestConfig{fill_timing, ..Default::default()};
+        let backtest = Backtest::with_config(quotes.clone(), strategy.clone(), 1200., config)?;
+        let trades = backtest.trades();
+        assert_eq!(trades.len(),1);
+        assert_eq!((trades[0].quantity,trades[0].entry_price,trades[0].exit_price),(quantity,entry_price,exit_price));
+        assert_eq!(trades[0].entry_time,quotes.datetime[entry_bar]);
+        assert_eq!(backtest.position()[entry_bar],quantity);
+    }
+    Ok(())
+}
+
+#[test]
+fn quantity_rules()->Result<(),Box<dyn Error>>{
+    let nearest = QuantityRule{min_quantity:0., step:0.5, rounding:Rounding::Nearest};
+    assert_eq!((nearest.round(10.2),nearest.round(10.3)),(10.,10.5));
+    let up = QuantityRule{rounding:Rounding::Up, ..Default::default()};
+    assert_eq!((up.round(10.2),up.round(10.)),(11.,10.));
+    assert_eq!((QuantityRule::lots(30.).round(29.),QuantityRule::lots(30.).round(100.)),(0.,90.));
+    assert_eq!(QuantityRule::fractional().affordable(1005.,10.,&Commission::default(),0.),100.5);
+    assert_eq!(QuantityRule::default().affordable(1005.,10.,&Commission::Rate(0.01),0.),99.);
+
+    let quotes = synthetic_quotes(vec![10.,10.,12.,11.,9.,9.]);
+    let strategy = Strategy::new("sizing", vec![BUY,BUY,SHORTSELL,SHORTSELL,NULL,NULL], None);
+    let expected = [
+        (QuantityRule::default(),100.,-100.),
+        (QuantityRule::fractional(),100.5,-100.5),
+        (QuantityRule::lots(30.),90.,-90.),
+        (up,101.,-101.),
+    ];
+    for (quantity_rule,long,short) in expected{
+        let config = BacktestConfig{quantity_rule, ..Default::default()};
+        let backtest = Backtest::with_config(quotes.clone(), strategy.clone(), 1005., config)?;
+        assert_eq!((backtest.position()[1],backtest.position()[3]),(long,short));
+    }
+    Ok(())
+}
+
+#[test]
+fn target_weights()->Result<(),Box<dyn Error>>{
+    assert_eq!((BUY.weight(),SHORTSELL.weight(),NULL.weight()),(1.,-1.,0.));
+    let quotes = synthetic_quotes(vec![10.,10.,12.,11.,9.,9.]);
+    let strategy = Strategy::from_weights("weights", vec![0.5,1.,0.5,-0.5,0.,0.], None);
+    assert_eq!(strategy.choices,vec![BUY,BUY,BUY,SHORTSELL,NULL,NULL]);
+    assert_eq!(strategy.invert().weights(),vec![-0.5,-1.,-0.5,0.5,0.,0.]);
+    let backtest = Backtest::new(quotes, strategy, 1000., Commission::default())?;
+    assert_eq!(backtest.position(),vec![0.,50.,91.,45.,-51.,0.]);
+    let trades = backtest.trades();
+    assert_eq!(trades.len(),3);
+    //scaling out closes part of the position at its average entry price
+    assert_eq!((trades[0].side,trades[0].quantity,trades[0].exit_price,trades[0].bars_held),(Side::Long,46.,11.,2));
+    assert!((trades[0].entry_price-992./91.).abs()<1e-12);
+    assert_eq!((trades[1].quantity,trades[1].exit_price),(45.,9.));
+    assert_eq!((trades[2].side,trades[2].quantity,trades[2].pnl),(Side::Short,51.,0.));
+    assert!((backtest.account().last().unwrap()-919.).abs()<1e-9);
+    Ok(())
+}
+
+///header and rows of a csv file
+///a Backtest exported with to_csv()
+struct Export{
+    header:Vec<String>,
+    rows:Vec<Vec<String>>,
+}
+
+impl Export{
+    ///exports backtest to the temporary file name and reads it back
+    fn read(backtest:&Backtest, name:&str)->Result<Self,Box<dyn Error>>{
+        let path = std::env::temp_dir().join(name);
+        backtest.to_csv(path.to_str().unwrap())?;
+        let mut reader = csv::Reader::from_path(path)?;
+        let header = reader.headers()?.iter().map(|x|x.to_string()).collect();
+        let rows = reader.records().map(|row|row.map(|row|row.iter().map(|x|x.to_string()).collect())).collect::<Result<_,_>>()?;
+        Ok(Export{header, rows})
+    }
+    fn column(&self, name:&str)->Vec<&str>{
+        let j = self.header.iter().position(|x|x==name).unwrap();
+        self.rows.iter().map(|row|row[j].as_str()).collect()
+    }
+    fn values(&self, name:&str)->Vec<f64>{
+        self.column(name).iter().map(|x|x.parse().unwrap()).collect()
+    }
+}
+
+#[test]
+fn csv_export()->Result<(),Box<dyn Error>>{
+    let mut quotes = synthetic_quotes(vec![10.,10.,12.,12.,12.]);
+    quotes.close = vec![11.,12.,13.,13.,13.];
+    let strategy = Strategy::new("csv", vec![BUY,BUY,NULL,NULL,NULL], None);
+    //filled at the close of the bar of the choice: 90 shares bought at 11 (1% commission) and sold at 13
+    let config = BacktestConfig{fill_timing:FillTiming::SameClose, commission:Commission::Rate(0.01), ..Default::default()};
+    let backtest = Backtest::with_config(quotes, strategy, 1000., config)?;
+    let export = Export::read(&backtest, "csv_export.csv")?;
+    assert_eq!(export.values("POSITION"),vec![90.,90.,0.,0.,0.]);
+    assert_eq!(export.values("FLOW"),vec![-990.,0.,1170.,0.,0.]);
+    let commission = export.values("COMMISSION");
+    assert!((commission[0]-9.9).abs() < 1e-9 && (commission[2]-11.7).abs() < 1e-9);
+    assert_eq!(commission[1]+commission[3]+commission[4],0.);
+    assert_eq!(export.values("HIST_PRICE"),vec![11.,11.,13.,13.,13.]);
+    assert_eq!(backtest.fills().len(),2);
+
+    //a deposit is a cash flow, not a commission
+    let mut quotes = synthetic_quotes(vec![10.,10.,12.,12.,12.]);
+    quotes.close = vec![11.,12.,13.,13.,13.];
+    let strategy = Strategy::new("csv", vec![BUY,BUY,NULL,NULL,NULL], None);
+    let config = BacktestConfig{fill_timing:FillTiming::SameClose, cash_flows:vec![(quotes.datetime[1],500.)], ..Default::default()};
+    let backtest = Backtest::with_config(quotes, strategy, 1000., config)?;
+    let export = Export::read(&backtest, "csv_export_flows.csv")?;
+    assert_eq!(export.values("CASH_FLOW"),vec![0.,500.,0.,0.,0.]);
+    assert_eq!(export.values("COMMISSION"),vec![0.;5]);
+    assert_eq!(export.values("FLOW"),vec![-990.,0.,1170.,0.,0.]);
+
+    //slippage of half a point: 86 shares bought at 11.5 and sold at 12.5
+    let mut quotes = synthetic_quotes(vec![10.,10.,12.,12.,12.]);
+    quotes.close = vec![11.,12.,13.,13.,13.];
+    let strategy = Strategy::new("csv", vec![BUY,BUY,NULL,NULL,NULL], None);
+    let config = BacktestConfig{fill_timing:FillTiming::SameClose, slippage:Slippage::Ticks{ticks:1., tick_size:0.5}, ..Default::default()};
+    let backtest = Backtest::with_config(quotes, strategy, 1000., config)?;
+    let export = Export::read(&backtest, "csv_export_slippage.csv")?;
+    assert_eq!(export.values("SLIPPAGE"),vec![43.,0.,43.,0.,0.]);
+    assert_eq!(export.values("COMMISSION"),vec![0.;5]);
+    assert_eq!(export.values("FLOW"),vec![-989.,0.,1075.,0.,0.]);
+    assert_eq!(export.column("FILL_PRICE"),vec!["11.5","","12.5","",""]);
+    assert_eq!(export.values("HIST_PRICE"),vec![11.5,11.5,12.5,12.5,12.5]);
+    assert_eq!(backtest.fills()[0].slippage,43.);
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use chrono::{DateTime, Duration, FixedOffset};
+use rs_backtester::datas::Data;
+
+///daily bars with the given opens (close = open, high/low = open +/- 1)
+pub fn synthetic_quotes(open:Vec<f64>)->Data{
+    let start:DateTime<FixedOffset> = "2024-01-01 14:30:00 +00:00".parse().unwrap();
+    let length = open.len();
+    Data{
+        ticker:"SYN".to_string(),
+        datetime:(0..length).map(|i|start+Duration::days(i as i64)).collect(),
+        high:open.iter().map(|x|x+1.).collect(),
+        low:open.iter().map(|x|x-1.).collect(),
+        close:open.clone(),
+        open,
+        volume:vec![1000.;length],
+    }
+}
@@ -1,13 +1,121 @@
 use std::error::Error;
-use rs_backtester::datas::Data;
+use chrono::FixedOffset;
+use rs_backtester::datas::{Column, Data, LoadConfig};
+use rs_backtester::providers::{DataProvider, ReplayProvider, YahooProvider};
+use rs_backtester::BacktestError;
 
 
 #[test]
-fn download_test()->Result<(), Box<dyn Error>>{
-    let a:Data=Data::new_from_yahoo("AAPL","1d","1mo")?;
+fn fixture_save_load_test()->Result<(), Box<dyn Error>>{
+    let a:Data=ReplayProvider::new("tests/fixtures").fetch("AAPL","1d","1mo")?;
     assert_eq!(a.ticker(),"AAPL");
-    a.save("savedata.csv")?;
-    let b = Data::load("savedata.csv","test")?;
+    let path = std::env::temp_dir().join("savedata.csv");
+    a.save(path.to_str().unwrap())?;
+    let b = Data::load(path.to_str().unwrap(),"test")?;
     assert_eq!(b.ticker(),"test");
+    assert_eq!(b.close(),a.close());
+    assert_eq!(b.volume(),a.volume());
+    //ranges outside of the bars are errors
+    let length = a.close().len();
+    assert_eq!(a.slice(2,length)?.close(),a.close()[2..].to_vec());
+    assert!(matches!(a.slice(0,length+1), Err(BacktestError::InvalidParameter(_))));
+    assert!(matches!(a.slice(3,2), Err(BacktestError::InvalidParameter(_))));
+    Ok(())
+}
+
+//requires network access: cargo test -- --ignored download_test
+#[test]
+#[ignore]
+fn download_test()->Result<(), Box<dyn Error>>{
+    let directory = std::env::temp_dir().join("rs_backtester_yahoo");
+    std::fs::create_dir_all(&directory)?;
+    let recorder = ReplayProvider::recording(&directory, YahooProvider);
+    let _ = std::fs::remove_file(recorder.fixture_path("AAPL","1d","1mo"));
+    let a = recorder.fetch("AAPL","1d","1mo")?;
+    assert_eq!(a.ticker(),"AAPL");
+    assert!(!a.close().is_empty());
+    assert_eq!(a.volume().len(),a.close().len());
+    assert!(a.volume().iter().any(|&v|v>0.));
+    let replayed = ReplayProvider::new(&directory).fetch("AAPL","1d","1mo")?;
+    assert_eq!(replayed.close(),a.close());
+    assert_eq!(replayed.volume(),a.volume());
+    Ok(())
+}
+
+//regenerates tests/fixtures/AAPL_1d_1mo.csv from yahoo (requires network access): cargo test -- --ignored record_fixture
+#[test]
+#[ignore]
+fn record_fixture()->Result<(), Box<dyn Error>>{
+    //the fixture is only replaced once the download succeeded
+    let a = YahooProvider.fetch("AAPL","1d","1mo")?;
+    assert!(a.volume().iter().all(|&v|v>0.));
+    let path = ReplayProvider::new("tests/fixtures").fixture_path("AAPL","1d","1mo");
+    a.save(path.to_str().unwrap())?;
+    Ok(())
+}
+#[test]
+fn volume_roundtrip_test()->Result<(), Box<dyn Error>>{
+    let datetime = vec!["2024-12-02 14:30:00 +00:00".parse()?,"2024-12-03 14:30:00 +00:00".parse()?];
+    let a = Data{
+        ticker:"TEST".to_string(),
+        datetime,
+        open:vec![10.,11.],
+        high:vec![12.,13.],
+        low:vec![9.,10.],
+        close:vec![11.,12.],
+        volume:vec![1500.,2500.],
+    };
+    let path = std::env::temp_dir().join("volume_roundtrip.csv");
+    a.save(path.to_str().unwrap())?;
+    let b = Data::load(path.to_str().unwrap(),"TEST")?;
+    assert_eq!(b.volume(),vec![1500.,2500.]);
+    //files without a volume column still load
+    let c = Data::load("GOOGLE.csv","GOOG")?;
+    assert!(c.volume().iter().all(|&v|v==0.));
+    //the volume column is found by its name (a yahoo export), not by its position
+    let path = std::env::temp_dir().join("yahoo_export.csv");
+    std::fs::write(&path,"Date,Open,High,Low,Close,Adj Close,Volume\n\
+        2024-12-02,237.27,240.79,237.16,239.59,238.85,48137100\n\
+        2024-12-03,239.81,242.76,238.90,242.65,241.90,38861000\n")?;
+    let d = Data::load(path.to_str().unwrap(),"AAPL")?;
+    assert_eq!(d.close(),vec![239.59,242.65]);
+    assert_eq!(d.volume(),vec![48137100.,38861000.]);
+    //a sixth column which isn't the volume is an error instead of being read as volume
+    std::fs::write(&path,"Date,Open,High,Low,Close,Adj Close\n2024-12-02,237.27,240.79,237.16,239.59,238.85\n")?;
+    assert!(matches!(Data::load(path.to_str().unwrap(),"AAPL"), Err(BacktestError::Parse(_))));
+    Ok(())
+}
+
+#[test]
+fn load_config_test()->Result<(), Box<dyn Error>>{
+    let path = std::env::temp_dir().join("broker_export.csv");
+    std::fs::write(&path,"Close;Adj Close;Low;High;Open;Vol;Day\n\
+        100;50;98;104;99;1200;02/12/2024\n\
+        102;51;99;103;100;;03/12/2024\n")?;
+    let config = LoadConfig{
+        delimiter:b';',
+        date:Column::Name("day".to_string()),
+        open:Column::Name("Open".to_string()),
+        high:Column::Name("High".to_string()),
+        low:Column::Name("Low".to_string()),
+        close:Column::Name("Close".to_string()),
+        volume:Some(Column::Name("Vol".to_string())),
+        adj_close:Some(Column::Name("Adj Close".to_string())),
+        date_format:Some("%d/%m/%Y".to_string()),
+        timezone:FixedOffset::west_opt(5*3600).unwrap(),
+        ..Default::default()
+    };
+    let a = Data::load_with(path.to_str().unwrap(),"BRK",&config)?;
+    assert_eq!(a.timestamps()[1].to_rfc3339(),"2024-12-03T00:00:00-05:00");
+    assert_eq!(a.close(),vec![50.,51.]);
+    assert_eq!(a.open(),vec![49.5,50.]);
+    assert_eq!(a.volume(),vec![1200.,0.]);
+    //a wrong layout is reported as an error instead of a panic
+    assert!(Data::load(path.to_str().unwrap(),"BRK").is_err());
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn missing_file_test(){
+    assert!(matches!(Data::load("missing_file.csv","test"), Err(BacktestError::Csv(_))));
+}
@@ -0,0 +1,117 @@
+use std::error::Error;
+use rs_backtester::backtester::{Backtest, BacktestConfig, Commission};
+use rs_backtester::events::{self, Context, Fill};
+use rs_backtester::execution::FillTiming;
+use rs_backtester::BacktestError;
+use rs_backtester::orders::{OrderRequest, OrderType, TimeInForce};
+use rs_backtester::providers::{DataProvider, ReplayProvider};
+use rs_backtester::orders::Order::BUY;
+use rs_backtester::series::Series;
+use rs_backtester::stops::{Stop, StopRules};
+use rs_backtester::strategies::{sma_cross, Strategy};
+use rs_backtester::trades::ExitReason;
+mod common;
+use common::synthetic_quotes;
+
+///goes long on the first bar and exits two bars after the entry is filled
+struct HoldTwoBars{
+    entry:Option<usize>,
+    fills:usize,
+}
+
+impl events::Strategy for HoldTwoBars{
+    fn on_bar(&mut self, context:&Context){
+        assert_eq!(context.close().len(),context.bar()+1);
+        match self.entry{
+            None if context.bar() == 0=>context.target(1.),
+            Some(entry) if context.bar() == entry+2=>{
+                assert!(context.position() > 0.);
+                context.target(0.);
+            }
+            _=>{}
+        }
+    }
+    fn on_fill(&mut self, fill:&Fill){
+        self.fills += 1;
+        if fill.quantity > 0. {self.entry = Some(fill.bar);}
+    }
+}
+
+#[test]
+fn event_driven_strategy()->Result<(),Box<dyn Error>>{
+    let quotes = synthetic_quotes(vec![10.,10.,12.,11.,9.,9.]);
+    let mut strategy = HoldTwoBars{entry:None, fills:0};
+    let backtest = Backtest::event_driven(quotes, &mut strategy, 1000., BacktestConfig::default())?;
+    assert_eq!(backtest.position(),vec![0.,100.,100.,100.,0.,0.]);
+    assert_eq!(backtest.trades()[0].exit_price,9.);
+    assert_eq!((strategy.entry,strategy.fills),(Some(1),2));
+    assert_eq!(backtest.strategy().weights(),vec![1.,1.,1.,0.,0.,0.]);
+    Ok(())
+}
+
+#[test]
+fn vectorized_bridge()->Result<(),Box<dyn Error>>{
+    let quotes = ReplayProvider::new("tests/fixtures").fetch("AAPL","1d","1mo")?;
+    let last = quotes.close[5];
+    let strategy = sma_cross(quotes.clone(),3,5)?
+        .with_bracket(5,-1.,OrderType::Limit(last*1.01),TimeInForce::Bars(3),last*1.05,last*0.95);
+    for fill_timing in [FillTiming::NextOpen,FillTiming::SameClose]{
+        let config = BacktestConfig{fill_timing, commission:Commission::Rate(0.001), ..Default::default()};
+        let vectorized = Backtest::with_config(quotes.clone(), strategy.clone(), 1e5, config.clone())?;
+        let events = Backtest::event_driven(quotes.clone(), &mut strategy.clone(), 1e5, config)?;
+        assert_eq!(vectorized.position(),events.position());
+        assert_eq!(vectorized.account(),events.account());
+        assert_eq!(vectorized.trades(),events.trades());
+        assert_eq!(events.strategy().choices,strategy.choices);
+    }
+    //stops and indicator of the vectorized Strategy are kept
+    let quotes = synthetic_quotes(vec![10.;6]);
+    let strategy = Strategy::new("stopped", vec![BUY;6], Some(vec![Series::missing(6)]))
+        .with_stops(StopRules{stop_loss:Some(Stop::Percent(0.05)), ..Default::default()});
+    let vectorized = Backtest::with_config(quotes.clone(), strategy.clone(), 1e3, BacktestConfig::default())?;
+    let events = Backtest::event_driven(quotes, &mut strategy.clone(), 1e3, BacktestConfig::default())?;
+    assert_eq!(vectorized.position(),vec![0.,100.,0.,0.,0.,0.]);
+    assert_eq!(vectorized.position(),events.position());
+    assert_eq!(vectorized.trades(),events.trades());
+    assert_eq!(events.trades()[0].exit_reason,ExitReason::StopLoss);
+    assert_eq!(events.strategy().stops,strategy.stops);
+    assert_eq!(events.strategy().indicator,strategy.indicator);
+    Ok(())
+}
+
+///submits request on bar 1 and records whether it was accepted
+struct Submits{
+    request:OrderRequest,
+    accepted:Option<bool>,
+}
+
+impl events::Strategy for Submits{
+    fn on_bar(&mut self, context:&Context){
+        if context.bar() == 1 {self.accepted = Some(context.submit(self.request).is_ok());}
+    }
+}
+
+struct NanTarget;
+
+impl events::Strategy for NanTarget{
+    fn on_bar(&mut self, context:&Context){context.target(f64::NAN);}
+}
+
+#[test]
+fn invalid_commands()->Result<(),Box<dyn Error>>{
+    let quotes = synthetic_quotes(vec![10.;4]);
+    let valid = OrderRequest::new(0, 1., OrderType::MarketOnOpen, TimeInForce::Day);
+    let mut strategy = Submits{request:valid, accepted:None};
+    let backtest = Backtest::event_driven(quotes.clone(), &mut strategy, 1000., BacktestConfig::default())?;
+    assert_eq!((strategy.accepted,backtest.position()),(Some(true),vec![0.,0.,100.,100.]));
+    //a NaN target or a parent not submitted before the order is an error instead of a NaN position
+    for request in [OrderRequest{target:f64::NAN, ..valid}, OrderRequest{parent:Some(0), ..valid}]{
+        let mut strategy = Submits{request, accepted:None};
+        let result = Backtest::event_driven(quotes.clone(), &mut strategy, 1000., BacktestConfig::default());
+        assert_eq!(strategy.accepted,Some(false));
+        assert!(matches!(result, Err(BacktestError::InvalidParameter(_))));
+    }
+    //so is a NaN target weight
+    assert!(matches!(Backtest::event_driven(quotes, &mut NanTarget, 1000., BacktestConfig::default()), Err(BacktestError::InvalidParameter(_))));
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use std::error::Error;
+use rs_backtester::datas::Data;
+use rs_backtester::lookahead::check_lookahead;
+use rs_backtester::orders::Order::{BUY, NULL};
+use rs_backtester::providers::{DataProvider, ReplayProvider};
+use rs_backtester::strategies::{rsi_strategy, sma_cross, Strategy};
+mod common;
+use common::synthetic_quotes;
+
+///goes long when the next close is higher
+fn peeking(quotes:Data)->rs_backtester::Result<Strategy>{
+    let close = quotes.close();
+    let choices = (0..close.len()).map(|i|if i+1 < close.len() && close[i+1] > close[i] {BUY} else {NULL}).collect();
+    Ok(Strategy::new("peeking", choices, None))
+}
+
+#[test]
+fn lookahead()->Result<(),Box<dyn Error>>{
+    let quotes = ReplayProvider::new("tests/fixtures").fetch("AAPL","1d","1mo")?;
+    assert!(check_lookahead(&quotes, 5, |q|sma_cross(q,3,5))?.is_causal());
+    assert!(check_lookahead(&quotes, 4, |q|rsi_strategy(q,3))?.is_causal());
+
+    let report = check_lookahead(&synthetic_quotes(vec![10.,10.,12.,11.,9.,9.]), 1, peeking)?;
+    assert_eq!((report.first(),report.bars),(Some(1),vec![1]));
+    Ok(())
+}
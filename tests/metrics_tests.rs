@@ -0,0 +1,31 @@
+use std::error::Error;
+use rs_backtester::metrics::{cagr, calmar, max_drawdown, returns, sharpe, sortino, volatility};
+use rs_backtester::providers::{CsvProvider, DataProvider};
+
+#[test]
+fn metrics_values(){
+    let equity = vec![100.,120.,90.,95.,130.,110.];
+    assert_eq!(max_drawdown(&equity),(0.25,2));
+    assert!((cagr(&[100.,121.],2.)-0.1).abs()<1e-12);
+    assert_eq!(calmar(0.1,0.25),0.4);
+    let r = returns(&[100.,110.,99.]);
+    assert!((r[0]-0.1).abs()<1e-12 && (r[1]+0.1).abs()<1e-12);
+    //mean 0.01, sample std 0.01, downside deviation sqrt(0.0001/3)
+    let r = vec![0.02,0.,0.01];
+    assert!((sharpe(&r,252.)-252f64.sqrt()).abs()<1e-9);
+    assert!((volatility(&r,252.)-0.01*252f64.sqrt()).abs()<1e-12);
+    assert_eq!(sortino(&r,252.),0.);
+    let r = vec![0.02,-0.01,0.02];
+    assert!((sortino(&r,1.)-0.01/(0.0001f64/3.).sqrt()).abs()<1e-9);
+}
+
+#[test]
+fn periods_per_year()->Result<(), Box<dyn Error>>{
+    let daily = CsvProvider::new(".").fetch("GOOGLE","1d","max")?;
+    assert_eq!(daily.periods_per_year(),252.);
+    let weekly_index:Vec<usize> = (0..daily.timestamps().len()).step_by(5).collect();
+    let mut weekly = daily.clone();
+    weekly.datetime = weekly_index.iter().map(|&i|daily.datetime[i]).collect();
+    assert_eq!(weekly.periods_per_year(),52.);
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use std::error::Error;
+use rs_backtester::backtester::{Backtest, BacktestConfig, Commission};
+use rs_backtester::orders::Order::{BUY,NULL};
+use rs_backtester::orders::{OrderRequest, OrderType, TimeInForce};
+use rs_backtester::stops::{Stop, StopRules};
+use rs_backtester::strategies::Strategy;
+use rs_backtester::trades::ExitReason;
+use rs_backtester::BacktestError;
+mod common;
+use common::synthetic_quotes;
+
+fn backtest(requests:Vec<OrderRequest>)->Result<Backtest,BacktestError>{
+    let strategy = Strategy::new("orders", vec![NULL;6], None).with_requests(requests);
+    Backtest::new(synthetic_quotes(vec![10.,10.,12.,11.,9.,9.]), strategy, 1000., Commission::default())
+}
+
+#[test]
+fn order_types()->Result<(),Box<dyn Error>>{
+    //buy stop reached by a gap: filled at the open
+    let stop = backtest(vec![OrderRequest::new(0,1.,OrderType::Stop(11.5),TimeInForce::GoodTillCancelled)])?;
+    assert_eq!(stop.position(),vec![0.,0.,83.,83.,83.,83.]);
+    assert_eq!((stop.trades()[0].entry_price,stop.trades()[0].exit_reason),(12.,ExitReason::EndOfData));
+
+    //buy limit: a day order expires, a 3 bars order is filled on the gap of bar 4
+    assert!(backtest(vec![OrderRequest::new(1,1.,OrderType::Limit(9.5),TimeInForce::Day)])?.trades().is_empty());
+    let limit = backtest(vec![OrderRequest::new(1,1.,OrderType::Limit(9.5),TimeInForce::Bars(3))])?;
+    assert_eq!((limit.trades()[0].entry_price,limit.position()[4]),(9.,111.));
+
+    //entry at the open, exit with a sell limit within the bar
+    let round_trip = backtest(vec![
+        OrderRequest::new(0,1.,OrderType::MarketOnOpen,TimeInForce::Day),
+        OrderRequest::new(1,0.,OrderType::Limit(12.5),TimeInForce::GoodTillCancelled),
+    ])?;
+    let trades = round_trip.trades();
+    assert_eq!((trades[0].entry_price,trades[0].exit_price,trades[0].pnl),(10.,12.5,250.));
+    assert_eq!(trades[0].exit_time,round_trip.quotes().datetime[2]);
+
+    let on_close = backtest(vec![OrderRequest::new(2,-1.,OrderType::MarketOnClose,TimeInForce::Day)])?;
+    assert_eq!((on_close.trades()[0].entry_price,on_close.position()[3]),(11.,-90.));
+
+    //stop triggered above the limit by the gap, filled at the limit when the bar comes back
+    let stop_limit = backtest(vec![OrderRequest::new(0,1.,OrderType::StopLimit{stop:11.5, limit:11.8},TimeInForce::GoodTillCancelled)])?;
+    assert_eq!(stop_limit.trades()[0].entry_price,11.8);
+
+    assert!(matches!(backtest(vec![OrderRequest::new(6,1.,OrderType::MarketOnOpen,TimeInForce::Day)]),Err(BacktestError::InvalidParameter(_))));
+    Ok(())
+}
+
+#[test]
+fn bracket_and_oco()->Result<(),Box<dyn Error>>{
+    let quotes = synthetic_quotes(vec![10.,10.,12.,11.,9.,9.]);
+    //the take profit fills on bar 2 and cancels the stop, which would close the new entry of bar 3 on bar 4
+    let strategy = Strategy::new("bracket", vec![NULL;6], None)
+        .with_requests(vec![OrderRequest::new(2,1.,OrderType::MarketOnOpen,TimeInForce::Day)])
+        .with_bracket(0,1.,OrderType::MarketOnOpen,TimeInForce::Day,9.5,12.5);
+    assert_eq!((strategy.requests[2].parent,strategy.requests[2].oco),(Some(1),Some(0)));
+    let backtest = Backtest::new(quotes.clone(), strategy, 1000., Commission::default())?;
+    let trades = backtest.trades();
+    assert_eq!(trades.len(),2);
+    assert_eq!((trades[0].entry_price,trades[0].exit_price,trades[0].exit_time),(10.,12.5,quotes.datetime[2]));
+    assert_eq!((trades[1].entry_price,trades[1].exit_reason),(11.,ExitReason::EndOfData));
+
+    //the stop of the bracket fills at the open of a gap
+    let strategy = Strategy::new("bracket", vec![NULL;6], None).with_bracket(0,1.,OrderType::MarketOnOpen,TimeInForce::Day,9.5,12.5);
+    let backtest = Backtest::new(synthetic_quotes(vec![10.,10.,9.,9.,9.,9.]), strategy, 1000., Commission::default())?;
+    assert_eq!((backtest.trades()[0].exit_price,backtest.position()[2]),(9.,0.));
+
+    //breakout on either side: the buy stop fills and cancels the sell stop
+    let strategy = Strategy::new("oco", vec![NULL;6], None).with_oco(vec![
+        OrderRequest::new(0,1.,OrderType::Stop(11.5),TimeInForce::GoodTillCancelled),
+        OrderRequest::new(0,-1.,OrderType::Stop(8.5),TimeInForce::GoodTillCancelled),
+    ]);
+    let backtest = Backtest::new(quotes, strategy, 1000., Commission::default())?;
+    assert_eq!(backtest.position(),vec![0.,0.,83.,83.,83.,83.]);
+    assert_eq!(backtest.trades().len(),1);
+
+    //the bracket entry (filled on bar 1) is closed by another order on bar 2: its stop is cancelled
+    //and does not close the new entry of bar 3 when the price gaps below it on bar 5
+    let quotes = synthetic_quotes(vec![10.,10.,10.,10.,9.,6.]);
+    let bracket = |strategy:Strategy|strategy.with_bracket(0,1.,OrderType::MarketOnOpen,TimeInForce::Day,7.5,20.);
+    let strategy = bracket(Strategy::new("bracket", vec![NULL;6], None).with_requests(vec![
+        OrderRequest::new(1,0.,OrderType::MarketOnOpen,TimeInForce::Day),
+        OrderRequest::new(2,1.,OrderType::MarketOnOpen,TimeInForce::Day),
+    ]));
+    let backtest = Backtest::new(quotes.clone(), strategy, 1000., Commission::default())?;
+    let trades = backtest.trades();
+    assert_eq!(trades.len(),2);
+    assert_eq!((trades[0].exit_time,trades[0].exit_reason),(quotes.datetime[2],ExitReason::Order));
+    assert_eq!((backtest.position()[5],trades[1].exit_reason),(100.,ExitReason::EndOfData));
+
+    //same when a signal closes the position on bar 3 (and buys again on bar 4)
+    let strategy = bracket(Strategy::new("bracket", vec![NULL,BUY,NULL,BUY,BUY,BUY], None));
+    let backtest = Backtest::new(quotes.clone(), strategy, 1000., Commission::default())?;
+    assert_eq!(backtest.trades()[0].exit_reason,ExitReason::Signal);
+    assert_eq!(backtest.position()[5],111.);
+
+    //same when a take profit rule closes the position on bar 2
+    let strategy = bracket(Strategy::new("bracket", vec![NULL;6], None)
+        .with_requests(vec![OrderRequest::new(2,1.,OrderType::MarketOnOpen,TimeInForce::Day)]));
+    let config = BacktestConfig{stops:StopRules{take_profit:Some(Stop::Absolute(0.5)), ..Default::default()}, ..Default::default()};
+    let backtest = Backtest::with_config(quotes, strategy, 1000., config)?;
+    assert_eq!(backtest.trades()[0].exit_reason,ExitReason::TakeProfit);
+    assert_eq!(backtest.position()[5],105.);
+    Ok(())
+}
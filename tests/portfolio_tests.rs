@@ -0,0 +1,47 @@
+use std::error::Error;
+use rs_backtester::backtester::Commission;
+use rs_backtester::datas::Data;
+use rs_backtester::portfolio::{align, PortfolioBacktest};
+use rs_backtester::providers::{CsvProvider, DataProvider, ReplayProvider};
+use rs_backtester::strategies::{buy_n_hold, do_nothing};
+
+#[test]
+fn portfolio_backtest()->Result<(), Box<dyn Error>>{
+    let aapl = ReplayProvider::new("tests/fixtures").fetch("AAPL","1d","1mo")?;
+    let mut other = aapl.clone();
+    other.ticker = "HALF".to_string();
+    other.open = other.open.iter().map(|x|x/2.).collect();
+    other.close = other.close.iter().map(|x|x/2.).collect();
+    let universe = vec![aapl.clone(),other.clone()];
+    let strategies = vec![buy_n_hold(aapl.clone()),buy_n_hold(other.clone())];
+    let portfolio = PortfolioBacktest::new(universe.clone(), strategies, vec![0.5,0.5], 100000., Commission::default())?;
+    assert_eq!(portfolio.tickers(),vec!["AAPL".to_string(),"HALF".to_string()]);
+    //same price path, half the price: twice the shares
+    assert_eq!(portfolio.position(0)[1],(50000./aapl.open()[1]) as i64 as f64);
+    assert_eq!(portfolio.position(1)[1],(100000./aapl.open()[1]) as i64 as f64);
+    assert!(portfolio.cash().iter().all(|&c|c>=0.));
+    let networth = portfolio.networth();
+    assert_eq!(networth[0],100000.);
+    let last = networth.len()-1;
+    let expected = portfolio.cash()[last]+portfolio.market_value(0)[last]+portfolio.market_value(1)[last];
+    assert!((networth[last]-expected).abs()<1e-6);
+
+    //an idle asset keeps its weight in cash
+    let strategies = vec![buy_n_hold(aapl.clone()),do_nothing(other.clone())];
+    let portfolio = PortfolioBacktest::new(universe, strategies, vec![0.5,0.5], 100000., Commission::default())?;
+    assert!(portfolio.position(1).iter().all(|&p|p==0.));
+    assert!(portfolio.cash()[1]>50000.);
+    Ok(())
+}
+
+#[test]
+fn align_universe()->Result<(), Box<dyn Error>>{
+    let google = CsvProvider::new(".").fetch("GOOGLE","1d","1y")?;
+    let aapl = ReplayProvider::new("tests/fixtures").fetch("AAPL","1d","1mo")?;
+    let shifted:Data = google.slice(10,google.timestamps().len())?;
+    let aligned = align(&[google.clone(),shifted])?;
+    assert_eq!(aligned[0].timestamps(),aligned[1].timestamps());
+    assert_eq!(aligned[0].timestamps().len(),google.timestamps().len()-10);
+    assert!(align(&[google,aapl]).is_err());
+    Ok(())
+}
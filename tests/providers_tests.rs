@@ -0,0 +1,33 @@
+use std::error::Error;
+use rs_backtester::providers::{CsvProvider, DataProvider, MemoryProvider, ReplayProvider};
+
+#[test]
+fn csv_provider_test()->Result<(), Box<dyn Error>>{
+    let provider = CsvProvider::new(".");
+    let all = provider.fetch("GOOGLE","1d","max")?;
+    let month = provider.fetch("GOOGLE","1d","1mo")?;
+    assert_eq!(month.ticker(),"GOOGLE");
+    assert!(month.timestamps().len()>15 && month.timestamps().len()<25);
+    assert_eq!(month.close().last(),all.close().last());
+    assert!(provider.fetch("GOOGLE","1d","1xx").is_err());
+    Ok(())
+}
+
+#[test]
+fn memory_and_replay_provider_test()->Result<(), Box<dyn Error>>{
+    let fixture = ReplayProvider::new("tests/fixtures").fetch("AAPL","1d","1mo")?;
+    let mut memory = MemoryProvider::new();
+    memory.insert(fixture.clone());
+    assert!(memory.fetch("MSFT","1d","1mo").is_err());
+
+    let directory = std::env::temp_dir().join("rs_backtester_replay");
+    std::fs::create_dir_all(&directory)?;
+    let recorder = ReplayProvider::recording(&directory, memory);
+    let _ = std::fs::remove_file(recorder.fixture_path("AAPL","1d","1mo"));
+    let recorded = recorder.fetch("AAPL","1d","1mo")?;
+    assert!(recorder.fixture_path("AAPL","1d","1mo").exists());
+    let replayed = ReplayProvider::new(&directory).fetch("AAPL","1d","1mo")?;
+    assert_eq!(recorded.close(),replayed.close());
+    assert_eq!(replayed.close(),fixture.close());
+    Ok(())
+}
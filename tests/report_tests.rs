@@ -0,0 +1,56 @@
+use std::error::Error;
+use rs_backtester::backtester::{Backtest, BacktestConfig, Commission};
+use rs_backtester::orders::Order::{BUY, NULL, SHORTSELL};
+use rs_backtester::strategies::{do_nothing, Strategy};
+mod common;
+use common::synthetic_quotes;
+
+#[test]
+fn backtest_report()->Result<(),Box<dyn Error>>{
+    let quotes = synthetic_quotes(vec![10.,10.,12.,11.,9.,9.]);
+    let strategy = Strategy::new("report", vec![BUY,BUY,SHORTSELL,SHORTSELL,NULL,NULL], None);
+    let report = Backtest::new(quotes, strategy, 100000., Commission::default())?.report();
+    assert_eq!(report.strategy,"report");
+    assert_eq!(report.ticker,"SYN");
+    assert_eq!(report.trades,2);
+    assert_eq!(report.win_rate,1.);
+    assert!((report.best_trade-2./11.).abs()<1e-12);
+    assert!((report.worst_trade-0.1).abs()<1e-12);
+    assert!((report.exposure-4./6.).abs()<1e-12);
+    assert!((report.ret-0.3).abs()<1e-12);
+    assert!((report.max_drawdown-1./12.).abs()<1e-12);
+    assert_eq!(report.max_drawdown_duration,1);
+    assert!(report.sharpe>0. && report.sortino>report.sharpe);
+    Ok(())
+}
+
+#[test]
+fn initial_capital_and_cash_flows()->Result<(),Box<dyn Error>>{
+    let quotes = synthetic_quotes(vec![10.,10.,12.,11.,9.,9.]);
+    let strategy = Strategy::new("report", vec![BUY,BUY,SHORTSELL,SHORTSELL,NULL,NULL], None);
+    let small = Backtest::new(quotes.clone(), strategy.clone(), 1000., Commission::default())?;
+    assert_eq!(small.initial_capital(),1000.);
+    assert!((small.report().ret-0.3).abs()<1e-12);
+
+    //a deposit is neither a gain nor a loss
+    let config = BacktestConfig{cash_flows:vec![(quotes.datetime[2],500.),(quotes.datetime[4],-200.)], ..Default::default()};
+    let idle = Backtest::with_config(quotes.clone(), do_nothing(quotes.clone()), 1000., config)?;
+    assert_eq!(idle.networth(),vec![1000.,1000.,1500.,1500.,1300.,1300.]);
+    assert_eq!(idle.net_contributions(),1300.);
+    assert!(idle.returns().iter().all(|&r|r==0.));
+    let report = idle.report();
+    assert_eq!((report.ret,report.max_drawdown,report.cagr),(0.,0.,0.));
+    assert_eq!(idle.contributions(),vec![1000.,1000.,1500.,1500.,1300.,1300.]);
+
+    //returns stay finite after everything was withdrawn
+    let config = BacktestConfig{cash_flows:vec![(quotes.datetime[2],-1000.),(quotes.datetime[4],500.)], ..Default::default()};
+    let emptied = Backtest::with_config(quotes.clone(), do_nothing(quotes.clone()), 1000., config)?;
+    assert_eq!(emptied.networth(),vec![1000.,1000.,0.,0.,500.,500.]);
+    assert_eq!(emptied.returns(),vec![0.;6]);
+    let config = BacktestConfig{cash_flows:vec![(quotes.datetime[2],-1000.)], ..Default::default()};
+    let withdrawn = Backtest::with_config(quotes.clone(), do_nothing(quotes), 1000., config)?;
+    assert_eq!(withdrawn.net_contributions(),0.);
+    let report = withdrawn.report();
+    assert_eq!((report.ret,report.cagr,report.max_drawdown),(0.,0.,0.));
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use std::error::Error;
+use rs_backtester::backtester::{Backtest, BacktestConfig};
+use rs_backtester::metrics;
+use rs_backtester::orders::Order::{BUY, NULL};
+use rs_backtester::sizing::PositionSizer;
+use rs_backtester::strategies::Strategy;
+use rs_backtester::trades::{ExitReason, Side, Trade};
+mod common;
+use common::synthetic_quotes;
+
+fn trade(pnl:f64)->Trade{
+    let time = "2024-01-01 14:30:00 +00:00".parse().unwrap();
+    Trade{side:Side::Long, entry_time:time, exit_time:time, quantity:1., entry_price:100., exit_price:100.+pnl,
+        commission:0., slippage:0., pnl, bars_held:1, exit_reason:ExitReason::Signal}
+}
+
+#[test]
+fn position_sizers()->Result<(),Box<dyn Error>>{
+    let quotes = synthetic_quotes(vec![10.,11.,10.,11.,10.,10.]);
+    assert_eq!(PositionSizer::AllIn.budget(1000.,&quotes,4,&[]),1000.);
+    assert_eq!(PositionSizer::FixedCash(300.).budget(1000.,&quotes,4,&[]),300.);
+    assert_eq!(PositionSizer::FixedCash(3000.).budget(1000.,&quotes,4,&[]),1000.);
+    assert_eq!(PositionSizer::FixedFraction(0.25).budget(1000.,&quotes,4,&[]),250.);
+
+    let volatility = metrics::volatility(&metrics::returns(&quotes.close[0..4]),252.);
+    let budget = PositionSizer::VolatilityTarget{target:0.01, period:3}.budget(1000.,&quotes,4,&[]);
+    assert!((budget-0.01/volatility*1000.).abs()<1e-9);
+    assert_eq!(PositionSizer::VolatilityTarget{target:0.01, period:3}.budget(1000.,&quotes,3,&[]),0.);
+
+    let flat = synthetic_quotes(vec![10.;6]);
+    let budget = PositionSizer::AtrRisk{risk:0.01, multiple:2., period:3}.budget(1000.,&flat,4,&[]);
+    assert!((budget-25.).abs()<1e-9);
+
+    let kelly = PositionSizer::Kelly{fraction:0.5, min_trades:3, fallback:0.1};
+    let trades = vec![trade(20.),trade(-10.),trade(20.)];
+    assert!((kelly.budget(1000.,&quotes,4,&trades[..2])-100.).abs()<1e-9);
+    assert!((kelly.budget(1000.,&quotes,4,&trades)-250.).abs()<1e-9);
+    assert_eq!(kelly.budget(1000.,&quotes,4,&vec![trade(-10.);3]),0.);
+    Ok(())
+}
+
+#[test]
+fn sized_backtest()->Result<(),Box<dyn Error>>{
+    let quotes = synthetic_quotes(vec![10.,10.,12.,11.,9.,9.]);
+    let strategy = Strategy::new("sizing", vec![BUY,BUY,NULL,NULL,NULL,NULL], None);
+    let config = BacktestConfig{position_sizer:PositionSizer::FixedFraction(0.25), ..Default::default()};
+    let backtest = Backtest::with_config(quotes.clone(), strategy.clone(), 1000., config)?;
+    assert_eq!(backtest.position()[1],25.);
+    assert_eq!(backtest.account()[1],750.);
+    assert_eq!(backtest.account().last(),Some(&1025.));
+
+    let config = BacktestConfig{position_sizer:PositionSizer::AtrRisk{risk:0.01, multiple:2., period:3}, ..Default::default()};
+    let backtest = Backtest::with_config(quotes, strategy, 1000., config)?;
+    assert!(backtest.trades().is_empty());
+    Ok(())
+}
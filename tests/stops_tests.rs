@@ -0,0 +1,62 @@
+use std::error::Error;
+use rs_backtester::backtester::{Backtest, BacktestConfig, Commission};
+use rs_backtester::orders::Order::{BUY, SHORTSELL};
+use rs_backtester::stops::{Stop, StopRules};
+use rs_backtester::strategies::Strategy;
+use rs_backtester::trades::ExitReason;
+mod common;
+use common::synthetic_quotes;
+
+#[test]
+fn stop_distances(){
+    let quotes = synthetic_quotes(vec![10.;6]);
+    assert_eq!(Stop::Percent(0.05).distance(10.,&quotes,1),Some(0.5));
+    assert_eq!(Stop::Absolute(2.).distance(10.,&quotes,1),Some(2.));
+    assert_eq!(Stop::Atr{multiple:1.5, period:3}.distance(10.,&quotes,4),Some(3.));
+    assert_eq!(Stop::Atr{multiple:1.5, period:3}.distance(10.,&quotes,3),None);
+}
+
+#[test]
+fn stop_loss_and_take_profit()->Result<(),Box<dyn Error>>{
+    let long = Strategy::new("long", vec![BUY;6], None);
+    let stop_loss = StopRules{stop_loss:Some(Stop::Percent(0.05)), ..Default::default()};
+
+    //intrabar: filled at the level, then out of the market while the signal does not change
+    let config = BacktestConfig{stops:stop_loss, ..Default::default()};
+    let backtest = Backtest::with_config(synthetic_quotes(vec![10.;6]), long.clone(), 1000., config)?;
+    let trades = backtest.trades();
+    assert_eq!(trades.len(),1);
+    assert_eq!((trades[0].exit_price,trades[0].exit_reason,trades[0].bars_held),(9.5,ExitReason::StopLoss,1));
+    assert_eq!(backtest.position(),vec![0.,100.,0.,0.,0.,0.]);
+    assert_eq!(backtest.account().last(),Some(&950.));
+
+    //gap through the level: filled at the open
+    let strategy = long.clone().with_stops(StopRules{stop_loss:Some(Stop::Absolute(1.)), ..Default::default()});
+    let backtest = Backtest::new(synthetic_quotes(vec![10.,10.,8.,8.,8.,8.]), strategy, 1000., Commission::default())?;
+    assert_eq!((backtest.trades()[0].exit_price,backtest.trades()[0].exit_reason),(8.,ExitReason::StopLoss));
+
+    //the rules of the strategy replace the ones of the config
+    let strategy = long.with_stops(StopRules{take_profit:Some(Stop::Percent(0.1)), ..Default::default()});
+    let config = BacktestConfig{stops:stop_loss, ..Default::default()};
+    let backtest = Backtest::with_config(synthetic_quotes(vec![10.,10.,10.5,12.,12.,12.]), strategy, 1000., config)?;
+    assert_eq!((backtest.trades()[0].exit_price,backtest.trades()[0].exit_reason),(11.,ExitReason::TakeProfit));
+    assert_eq!(backtest.trades()[0].exit_time,backtest.quotes().datetime[2]);
+
+    //shorts are stopped out by the high
+    let short = Strategy::new("short", vec![SHORTSELL;6], None).with_stops(StopRules{stop_loss:Some(Stop::Absolute(1.)), ..Default::default()});
+    let backtest = Backtest::new(synthetic_quotes(vec![10.;6]), short, 1000., Commission::default())?;
+    assert_eq!((backtest.trades()[0].exit_price,backtest.trades()[0].pnl),(11.,-100.));
+    Ok(())
+}
+
+#[test]
+fn trailing_stop()->Result<(),Box<dyn Error>>{
+    let stops = StopRules{stop_loss:Some(Stop::Percent(0.05)), trailing_stop:Some(Stop::Absolute(1.5)), ..Default::default()};
+    let strategy = Strategy::new("trailing", vec![BUY;6], None).with_stops(stops);
+    let backtest = Backtest::new(synthetic_quotes(vec![10.,10.,12.,14.,12.,12.]), strategy, 1000., Commission::default())?;
+    let trades = backtest.trades();
+    assert_eq!(trades.len(),1);
+    //highest high is 15 at the end of bar 3, bar 4 opens below 13.5
+    assert_eq!((trades[0].exit_price,trades[0].exit_reason,trades[0].pnl),(12.,ExitReason::TrailingStop,200.));
+    Ok(())
+}
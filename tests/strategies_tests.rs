@@ -1,11 +1,23 @@
-use rs_backtester::datas::Data;
+use rs_backtester::providers::{DataProvider, ReplayProvider};
 use std::error::Error;
-use rs_backtester::strategies::simple_sma;
+use rs_backtester::BacktestError;
+use rs_backtester::strategies::{simple_sma, sma_cross};
 
 #[test]
 fn strategies_tests()->Result<(), Box<dyn Error>>{
-    let quotes = Data::new_from_yahoo("AAPL","1d","1mo")?;
-    let sma_cross_strategy = simple_sma(quotes.clone(), 5);
-    sma_cross_strategy.to_csv("strategies.csv");
+    let quotes = ReplayProvider::new("tests/fixtures").fetch("AAPL","1d","1mo")?;
+    let sma_cross_strategy = simple_sma(quotes.clone(), 5)?;
+    sma_cross_strategy.to_csv(std::env::temp_dir().join("strategies.csv").to_str().unwrap())?;
+    //out of the market after the last bar
+    let length = quotes.close().len();
+    assert_eq!(sma_cross_strategy.weight(length-1),sma_cross_strategy.choices()[length-1].weight());
+    assert_eq!(sma_cross_strategy.weight(length),0.);
     Ok(())
-}
\ No newline at end of file
+}
+#[test]
+fn invalid_parameters_test()->Result<(), Box<dyn Error>>{
+    let quotes = ReplayProvider::new("tests/fixtures").fetch("AAPL","1d","1mo")?;
+    assert!(matches!(sma_cross(quotes.clone(), 20, 10), Err(BacktestError::InvalidParameter(_))));
+    assert!(matches!(simple_sma(quotes.clone(), 0), Err(BacktestError::InvalidParameter(_))));
+    Ok(())
+}
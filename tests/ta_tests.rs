@@ -1,12 +1,245 @@
-use rs_backtester::datas::Data;
+use rs_backtester::providers::{DataProvider, ReplayProvider};
 use std::error::Error;
-use rs_backtester::ta::{Indicator, rsi};
+use rs_backtester::datas::Data;
+use rs_backtester::ta::{Indicator, RsiMethod, rsi, rsi_with, stoch_rsi};
+mod common;
+use common::synthetic_quotes;
+use rs_backtester::series::Series;
+use rs_backtester::strategies::{sma_cross, ema_cross, ma_cross, macd_cross, stochastic_strategy};
+use rs_backtester::ta::{MovingAverage, Source, ema, wma, dema, tema, hma, kama, vwma};
+use rs_backtester::ta::{true_range, atr, bollinger, keltner, donchian, historical_volatility};
+use rs_backtester::ta::{macd, stochastic, williams_r, cci, roc, momentum};
+use rs_backtester::orders::Order::{BUY,SHORTSELL,NULL};
 #[test]
 fn indicator_tests()->Result<(), Box<dyn Error>>{
-    let quotes = &Data::new_from_yahoo("AAPL","1d","1mo")?;
-    let indicator = rsi(quotes,5);
-    println!("{:?}",indicator);
-    println!("{:?}",quotes.close());
-    let a = Indicator{indicator:indicator, quotes:quotes.clone()};
+    let quotes = &ReplayProvider::new("tests/fixtures").fetch("AAPL","1d","1mo")?;
+    let indicator = rsi(quotes,5)?;
+    let a = Indicator{indicator:indicator.clone(), quotes:quotes.clone()};
+    assert_eq!((a.indicator(),a.quotes().close()),(indicator,quotes.close()));
+    //RSI(5) of the 21 bars, first and last values computed separately
+    let close = |x:f64,y:f64|(x-y).abs() < 1e-9;
+    for (method,warmup,first,last) in [(RsiMethod::Wilder,5,97.35453686734571,37.12399355393288),
+        (RsiMethod::Cutler,5,97.35453686734571,30.364308942285675),(RsiMethod::OpenClose,4,77.59845580547487,55.31249180434715)]{
+        let series = rsi_with(quotes,5,method)?;
+        assert_eq!((series.len(),series.first_valid()),(21,Some(warmup)), "{:?}", method);
+        assert!(close(series[warmup].unwrap(),first) && close(series[20].unwrap(),last), "{:?}", method);
+    }
+    Ok(())
+}
+#[test]
+fn warmup_is_missing()->Result<(), Box<dyn Error>>{
+    //prices of a spread can be -1: they are values, not warm-up
+    let quotes = synthetic_quotes(vec![-1.,-1.,-3.,-1.,1.]);
+    let sma = rs_backtester::ta::sma(&quotes,2)?;
+    assert_eq!(sma, Series(vec![None,Some(-1.),Some(-2.),Some(-2.),Some(0.)]));
+    assert_eq!(sma.first_valid(), Some(1));
+    assert!(sma.to_vec()[0].is_nan());
+    assert_eq!(sma.format(0), "");
+    assert_eq!(rs_backtester::ta::sma(&quotes,10)?, Series::missing(5));
+    assert_eq!(Series::from(vec![f64::NAN,-1.]), Series(vec![None,Some(-1.)]));
+    let strategy = sma_cross(quotes, 1, 2)?;
+    assert_eq!(strategy.choices, vec![NULL,SHORTSELL,SHORTSELL,BUY,BUY]);
+    Ok(())
+}
+
+fn assert_series(series:&Series, expected:&[Option<f64>]){
+    assert_eq!(series.len(), expected.len());
+    for (value,expected) in series.iter().zip(expected.iter()){
+        match (value,expected){
+            (Some(value),Some(expected))=>assert!((value-expected).abs() < 1e-9, "{} != {}", value, expected),
+            _=>assert_eq!(value, *expected),
+        }
+    }
+}
+
+#[test]
+fn moving_averages()->Result<(), Box<dyn Error>>{
+    let quotes = synthetic_quotes(vec![1.,2.,3.,4.,5.,6.]);
+    //EMA(3): seeded with the SMA of the first 3 closes, then smoothing 0.5
+    assert_series(&ema(&quotes,3,Source::Close)?, &[None,None,Some(2.),Some(3.),Some(4.),Some(5.)]);
+    assert_series(&ema(&quotes,3,Source::High)?, &[None,None,Some(3.),Some(4.),Some(5.),Some(6.)]);
+    assert_series(&ema(&quotes,3,Source::Typical)?, &[None,None,Some(2.),Some(3.),Some(4.),Some(5.)]);
+    assert_series(&wma(&quotes,3,Source::Close)?, &[None,None,Some(14./6.),Some(20./6.),Some(26./6.),Some(32./6.)]);
+    //DEMA, TEMA and HMA remove the lag of a linear trend
+    assert_series(&dema(&quotes,2,Source::Close)?, &[None,None,Some(3.),Some(4.),Some(5.),Some(6.)]);
+    assert_series(&tema(&quotes,2,Source::Close)?, &[None,None,None,Some(4.),Some(5.),Some(6.)]);
+    assert_series(&hma(&quotes,4,Source::Close)?, &[None,None,None,None,Some(5.),Some(6.)]);
+    //KAMA(2,2,30) of a trend: efficiency 1, smoothing (2/3)^2, started from the close of bar 1
+    let mut kama_values = vec![None,None];
+    let mut last = 2.;
+    for close in [3.,4.,5.,6.]{
+        last += 4./9.*(close-last);
+        kama_values.push(Some(last));
+    }
+    assert_series(&kama(&quotes,2,2,30,Source::Close)?, &kama_values);
+    assert!(matches!(kama(&quotes,2,30,2,Source::Close), Err(rs_backtester::BacktestError::InvalidParameter(_))));
+    let mut quotes = synthetic_quotes(vec![1.,2.,3.,4.]);
+    quotes.volume = vec![1.,3.,0.,0.];
+    assert_series(&vwma(&quotes,2,Source::Close)?, &[None,Some(1.75),Some(2.),None]);
+    assert_series(&MovingAverage::Simple.calculate(&quotes,2,Source::Open)?, &[None,Some(1.5),Some(2.5),Some(3.5)]);
+    //not enough bars
+    assert_eq!(tema(&quotes,3,Source::Close)?, Series::missing(4));
+    assert_eq!(hma(&quotes,9,Source::Close)?, Series::missing(4));
+    Ok(())
+}
+
+#[test]
+fn moving_average_crosses()->Result<(), Box<dyn Error>>{
+    let quotes = synthetic_quotes(vec![5.,4.,3.,2.,3.,4.,5.]);
+    let strategy = ema_cross(quotes.clone(), 2, 3)?;
+    assert_eq!(strategy.name, "ema_cross_2_3");
+    assert_eq!(strategy.choices, vec![NULL,NULL,SHORTSELL,SHORTSELL,SHORTSELL,BUY,BUY]);
+    let strategy = ma_cross(quotes.clone(), MovingAverage::Hull, 2, 4, Source::Close)?;
+    assert_eq!(strategy.name, "hma_cross_2_4");
+    assert_eq!(strategy.indicator.as_ref().map(|indicator|indicator.len()), Some(2));
+    assert!(ma_cross(quotes, MovingAverage::Weighted, 3, 3, Source::Close).is_err());
+    Ok(())
+}
+
+#[test]
+fn volatility_indicators()->Result<(), Box<dyn Error>>{
+    //high/low = close +/- 1, the last bar gaps up
+    let quotes = synthetic_quotes(vec![10.,11.,12.,11.,13.]);
+    assert_series(&true_range(&quotes), &[Some(2.),Some(2.),Some(2.),Some(2.),Some(3.)]);
+    assert_series(&atr(&quotes,2)?, &[None,None,Some(2.),Some(2.),Some(2.5)]);
+    let bands = bollinger(&quotes,2,2.,Source::Close)?;
+    assert_series(&bands.upper, &[None,Some(11.5),Some(12.5),Some(12.5),Some(14.)]);
+    assert_series(&bands.middle, &[None,Some(10.5),Some(11.5),Some(11.5),Some(12.)]);
+    assert_series(&bands.lower, &[None,Some(9.5),Some(10.5),Some(10.5),Some(10.)]);
+    let bands = donchian(&quotes,2)?;
+    assert_series(&bands.upper, &[None,Some(12.),Some(13.),Some(13.),Some(14.)]);
+    assert_series(&bands.middle, &[None,Some(10.5),Some(11.5),Some(11.5),Some(12.)]);
+    assert_series(&bands.lower, &[None,Some(9.),Some(10.),Some(10.),Some(10.)]);
+    //EMA(2) of closes: 10.5, 11.5, 11.5-1/3, ...
+    let bands = keltner(&quotes,2,2,1.)?;
+    let ema = 11.5-1./3.;
+    assert_series(&bands.upper, &[None,None,Some(13.5),Some(ema+2.),Some(ema+2./3.*(13.-ema)+2.5)]);
+    assert_series(&bands.lower, &[None,None,Some(9.5),Some(ema-2.),Some(ema+2./3.*(13.-ema)-2.5)]);
+    let lines:Vec<Series> = bands.clone().into();
+    assert_eq!(lines, vec![bands.upper,bands.middle,bands.lower]);
+    let volatility = historical_volatility(&quotes,2)?;
+    let (a,b) = (0.1,1./11.);
+    assert_series(&volatility.values()[..3].to_vec().into(), &[None,None,Some((a-b)/2f64.sqrt()*252f64.sqrt())]);
+    assert!(historical_volatility(&quotes,1).is_err());
+    assert!(atr(&quotes,0).is_err());
+    assert_eq!(atr(&quotes,5)?, Series::missing(5));
+    Ok(())
+}
+
+#[test]
+fn oscillators()->Result<(), Box<dyn Error>>{
+    //high/low = close +/- 1, so the typical price is the close
+    let quotes = synthetic_quotes(vec![10.,11.,12.,11.,13.]);
+    //EMA(2) = 10.5, 11.5, 11.5-1/3, ... and EMA(3) = 11, 11, 12 from bar 2
+    let ema2 = [11.5, 11.5-1./3., 11.5-1./3.+2./3.*(13.-11.5+1./3.)];
+    let line = [ema2[0]-11., ema2[1]-11., ema2[2]-12.];
+    let signal = [(line[0]+line[1])/2., (line[0]+line[1])/2.+2./3.*(line[2]-(line[0]+line[1])/2.)];
+    let macd = macd(&quotes,2,3,2,Source::Close)?;
+    assert_series(&macd.line, &[None,None,Some(line[0]),Some(line[1]),Some(line[2])]);
+    assert_series(&macd.signal, &[None,None,None,Some(signal[0]),Some(signal[1])]);
+    assert_series(&macd.histogram, &[None,None,None,Some(line[1]-signal[0]),Some(line[2]-signal[1])]);
+    assert!(rs_backtester::ta::macd(&quotes,3,2,2,Source::Close).is_err());
+    let oscillator = stochastic(&quotes,3,1,2)?;
+    assert_series(&oscillator.k, &[None,None,Some(75.),Some(100./3.),Some(75.)]);
+    assert_series(&oscillator.d, &[None,None,None,Some(325./6.),Some(325./6.)]);
+    assert_series(&stochastic(&quotes,3,2,1)?.k, &[None,None,None,Some(325./6.),Some(325./6.)]);
+    assert_series(&williams_r(&quotes,3)?, &[None,None,Some(-25.),Some(-200./3.),Some(-25.)]);
+    assert_series(&cci(&quotes,3)?, &[None,None,Some(100.),Some(-50.),Some(100.)]);
+    assert_series(&roc(&quotes,2,Source::Close)?, &[None,None,Some(20.),Some(0.),Some(100./12.)]);
+    assert_series(&momentum(&quotes,2,Source::Close)?, &[None,None,Some(2.),Some(0.),Some(1.)]);
+    //flat prices: no range, no deviation
+    let flat = rs_backtester::datas::Data{high:vec![10.;5], low:vec![10.;5], ..synthetic_quotes(vec![10.;5])};
+    assert_eq!(williams_r(&flat,3)?, Series::missing(5));
+    assert_eq!(cci(&flat,3)?, Series::missing(5));
+    Ok(())
+}
+
+#[test]
+fn oscillator_strategies()->Result<(), Box<dyn Error>>{
+    let quotes = synthetic_quotes(vec![10.,11.,12.,11.,13.]);
+    let strategy = macd_cross(quotes.clone(),2,3,2)?;
+    assert_eq!(strategy.name, "macd_cross_2_3_2");
+    assert_eq!(strategy.choices, vec![NULL,NULL,NULL,SHORTSELL,BUY]);
+    assert_eq!(strategy.indicator.as_ref().map(|indicator|indicator.len()), Some(3));
+    let quotes = synthetic_quotes(vec![10.,12.,14.,8.,16.]);
+    //%K(2) with high/low +/- 1: 75, 75, 12.5, 90
+    let strategy = stochastic_strategy(quotes,2,1,1)?;
+    assert_eq!(strategy.choices, vec![NULL,NULL,NULL,BUY,SHORTSELL]);
+    Ok(())
+}
+
+#[test]
+fn rsi_methods()->Result<(), Box<dyn Error>>{
+    //close to close changes: 1, 1, -1, 2, -1
+    let quotes = synthetic_quotes(vec![10.,11.,12.,11.,13.,12.]);
+    //Wilder(2): gains/losses 1/0, 0.5/0.5, 1.25/0.25, 0.625/0.625
+    assert_series(&rsi(&quotes,2)?, &[None,None,Some(100.),Some(50.),Some(100.-100./6.),Some(50.)]);
+    assert_eq!(rsi(&quotes,2)?, rsi_with(&quotes,2,RsiMethod::default())?);
+    //Cutler(2): gains/losses 1/0, 0.5/0.5, 1/0.5, 1/0.5
+    assert_series(&rsi_with(&quotes,2,RsiMethod::Cutler)?, &[None,None,Some(100.),Some(50.),Some(200./3.),Some(200./3.)]);
+    assert_eq!(rsi(&synthetic_quotes(vec![10.;4]),2)?, Series(vec![None,None,Some(50.),Some(50.)]));
+    assert_eq!(rsi(&quotes,6)?, Series::missing(6));
+    //previous calculation: close-open differences 2, -1, 1, -1, 3 averaged over the bars with a gain (resp. a loss)
+    let mut quotes = synthetic_quotes(vec![10.,11.,12.,11.,13.]);
+    quotes.open = vec![8.,12.,11.,12.,10.];
+    assert_series(&rsi_with(&quotes,2,RsiMethod::OpenClose)?, &[None,Some(200./3.),Some(50.),Some(50.),Some(75.)]);
+    //flat bars (close = open) and bars without losses are values, not NaN
+    let flat = synthetic_quotes(vec![10.,11.,12.,11.]);
+    assert_eq!(rsi_with(&flat,2,RsiMethod::OpenClose)?, Series(vec![None,Some(50.),Some(50.),Some(50.)]));
+    quotes.open = vec![9.,10.,11.,10.,12.];
+    assert_eq!(rsi_with(&quotes,2,RsiMethod::OpenClose)?, Series(vec![None,Some(100.),Some(100.),Some(100.),Some(100.)]));
+    Ok(())
+}
+
+#[test]
+fn stochastic_rsi()->Result<(), Box<dyn Error>>{
+    //Wilder RSI(2): 100, 50, 83.33, 50 from bar 2
+    let quotes = synthetic_quotes(vec![10.,11.,12.,11.,13.,12.]);
+    let oscillator = stoch_rsi(&quotes,2,2,1,2)?;
+    assert_series(&oscillator.k, &[None,None,None,Some(0.),Some(100.),Some(0.)]);
+    assert_series(&oscillator.d, &[None,None,None,None,Some(50.),Some(50.)]);
+    assert_eq!(stoch_rsi(&synthetic_quotes(vec![10.;5]),2,2,1,1)?.k, Series::missing(5));
+    assert!(stoch_rsi(&quotes,2,0,1,1).is_err());
+    Ok(())
+}
+
+///reference values of indicators, by name
+type References = Vec<(String,Series)>;
+
+///reads a fixture of quotes followed by reference values of indicators (empty when missing)
+fn reference(path:&str)->Result<(Data,References),Box<dyn Error>>{
+    let mut reader = csv::Reader::from_path(path)?;
+    let header:Vec<String> = reader.headers()?.iter().map(|x|x.to_string()).collect();
+    let rows:Vec<csv::StringRecord> = reader.records().collect::<Result<_,_>>()?;
+    let column = |j:usize|rows.iter().map(|row|row[j].parse::<f64>().ok()).collect::<Vec<Option<f64>>>();
+    let prices = |j:usize|column(j).into_iter().map(|x|x.unwrap()).collect::<Vec<f64>>();
+    let quotes = Data{
+        ticker:"REF".to_string(),
+        datetime:rows.iter().map(|row|row[0].parse()).collect::<Result<_,_>>()?,
+        open:prices(1), high:prices(2), low:prices(3), close:prices(4),
+        volume:vec![0.;rows.len()],
+    };
+    Ok((quotes, (5..header.len()).map(|j|(header[j].clone(), Series(column(j)))).collect()))
+}
+
+#[test]
+fn oscillators_reference()->Result<(), Box<dyn Error>>{
+    //daily GOOG quotes (from GOOGLE.csv) with MACD(12,26,9), slow Stochastic(14,3,3), Williams %R(14), CCI(20),
+    //ROC(12) and Momentum(10) computed by a separate script following the textbook formulas (not published values)
+    let (quotes,expected) = reference("tests/fixtures/GOOG_oscillators.csv")?;
+    let macd = macd(&quotes,12,26,9,Source::Close)?;
+    let oscillator = stochastic(&quotes,14,3,3)?;
+    let calculated = [macd.line, macd.signal, macd.histogram, oscillator.k, oscillator.d, williams_r(&quotes,14)?, cci(&quotes,20)?,
+        roc(&quotes,12,Source::Close)?, momentum(&quotes,10,Source::Close)?];
+    assert_eq!(calculated.len(), expected.len());
+    for (series,(name,expected)) in calculated.iter().zip(expected.iter()){
+        assert_eq!(series.first_valid(), expected.first_valid(), "{}", name);
+        for (value,reference) in series.iter().zip(expected.iter()){
+            match (value,reference){
+                (Some(value),Some(reference))=>assert!((value-reference).abs() < 1e-8, "{}: {} != {}", name, value, reference),
+                _=>assert_eq!(value, reference, "{}", name),
+            }
+        }
+    }
     Ok(())
-}
\ No newline at end of file
+}